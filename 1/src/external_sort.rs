@@ -0,0 +1,285 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::num::NonZeroUsize;
+use std::ops::Range;
+
+/// How many location IDs each run reads from disk at a time while merging.
+const RUN_READ_SIZE: usize = 1024;
+
+/// Sorts a list too large to hold in memory by spilling sorted runs of at
+/// most `run_size` location IDs to a temporary file. Every run goes in the
+/// same file, so only one file is open however many runs there are.
+pub struct ExternalSorter {
+    run_size: usize,
+    buffer: Vec<u64>,
+    writer: BufWriter<File>,
+    /// The byte range of each run in the file.
+    runs: Vec<Range<u64>>,
+    len: u64,
+}
+
+impl ExternalSorter {
+    pub fn new(run_size: NonZeroUsize) -> io::Result<ExternalSorter> {
+        Ok(ExternalSorter {
+            run_size: run_size.get(),
+            buffer: Vec::new(),
+            writer: BufWriter::new(tempfile::tempfile()?),
+            runs: Vec::new(),
+            len: 0,
+        })
+    }
+
+    pub fn push(&mut self, location_id: u64) -> io::Result<()> {
+        self.buffer.push(location_id);
+        if self.buffer.len() >= self.run_size {
+            self.spill()?;
+        }
+
+        Ok(())
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        self.buffer.sort_unstable();
+
+        let start = self.len;
+        for location_id in self.buffer.drain(..) {
+            self.writer.write_all(&location_id.to_le_bytes())?;
+            self.len += 8;
+        }
+
+        self.runs.push(start..self.len);
+        Ok(())
+    }
+
+    /// Spills whatever is left in memory and returns every run.
+    pub fn finish(mut self) -> io::Result<SortedRuns> {
+        if !self.buffer.is_empty() {
+            self.spill()?;
+        }
+
+        Ok(SortedRuns {
+            file: self.writer.into_inner().map_err(|err| err.into_error())?,
+            runs: self.runs,
+        })
+    }
+}
+
+/// The spilled runs of one list, which can be merged as many times as needed.
+pub struct SortedRuns {
+    file: File,
+    runs: Vec<Range<u64>>,
+}
+
+impl SortedRuns {
+    /// K-way merges every run into one sorted stream.
+    pub fn merge(&self) -> io::Result<MergedRuns<'_>> {
+        let mut readers = Vec::with_capacity(self.runs.len());
+        let mut heap = BinaryHeap::with_capacity(self.runs.len());
+
+        for (i, run) in self.runs.iter().enumerate() {
+            let mut reader = RunReader {
+                file: &self.file,
+                unread: run.clone(),
+                buffer: Vec::new(),
+                cursor: 0,
+            };
+            if let Some(location_id) = reader.read_location_id()? {
+                heap.push(Reverse((location_id, i)));
+            }
+            readers.push(reader);
+        }
+
+        Ok(MergedRuns { readers, heap })
+    }
+}
+
+/// Reads one run back from the shared file. Each reader seeks to where it
+/// left off before reading more, so runs can be read in any order.
+struct RunReader<'a> {
+    file: &'a File,
+    unread: Range<u64>,
+    buffer: Vec<u8>,
+    cursor: usize,
+}
+
+impl RunReader<'_> {
+    fn read_location_id(&mut self) -> io::Result<Option<u64>> {
+        if self.cursor == self.buffer.len() {
+            let len = (self.unread.end - self.unread.start).min(RUN_READ_SIZE as u64 * 8);
+            if len == 0 {
+                return Ok(None);
+            }
+
+            self.buffer.resize(len as usize, 0);
+            let mut file = self.file;
+            file.seek(SeekFrom::Start(self.unread.start))?;
+            file.read_exact(&mut self.buffer)?;
+            self.unread.start += len;
+            self.cursor = 0;
+        }
+
+        let bytes = self.buffer[self.cursor..self.cursor + 8]
+            .try_into()
+            .unwrap();
+        self.cursor += 8;
+        Ok(Some(u64::from_le_bytes(bytes)))
+    }
+}
+
+/// The smallest unread location ID of each run is kept in a min-heap, along
+/// with the run it came from, so the next one to read is always known.
+pub struct MergedRuns<'a> {
+    readers: Vec<RunReader<'a>>,
+    heap: BinaryHeap<Reverse<(u64, usize)>>,
+}
+
+impl Iterator for MergedRuns<'_> {
+    type Item = io::Result<u64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((location_id, i)) = self.heap.pop()?;
+
+        match self.readers[i].read_location_id() {
+            Ok(Some(next_location_id)) => self.heap.push(Reverse((next_location_id, i))),
+            Ok(None) => {}
+            Err(err) => return Some(Err(err)),
+        }
+
+        Some(Ok(location_id))
+    }
+}
+
+/// Groups a sorted stream into `(location ID, count)` runs.
+struct CountRuns<I> {
+    location_ids: I,
+    next: Option<u64>,
+}
+
+impl<I: Iterator<Item = io::Result<u64>>> Iterator for CountRuns<I> {
+    type Item = io::Result<(u64, u64)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let location_id = match self.next.take() {
+            Some(location_id) => location_id,
+            None => match self.location_ids.next()? {
+                Ok(location_id) => location_id,
+                Err(err) => return Some(Err(err)),
+            },
+        };
+
+        let mut count = 1;
+        for next in self.location_ids.by_ref() {
+            match next {
+                Ok(next) if next == location_id => count += 1,
+                Ok(next) => {
+                    self.next = Some(next);
+                    break;
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+
+        Some(Ok((location_id, count)))
+    }
+}
+
+pub fn total_distance(
+    sorted_left: impl Iterator<Item = io::Result<u64>>,
+    sorted_right: impl Iterator<Item = io::Result<u64>>,
+) -> io::Result<u64> {
+    sorted_left
+        .zip(sorted_right)
+        .map(|(left, right)| Ok(left?.abs_diff(right?)))
+        .sum()
+}
+
+/// The same score as `calculate_similarity_score`, found by walking both
+/// sorted streams once.
+pub fn similarity_score(
+    sorted_left: impl Iterator<Item = io::Result<u64>>,
+    sorted_right: impl Iterator<Item = io::Result<u64>>,
+) -> io::Result<u64> {
+    let mut right_runs = CountRuns {
+        location_ids: sorted_right,
+        next: None,
+    };
+    let mut right_run = right_runs.next().transpose()?;
+
+    let left_runs = CountRuns {
+        location_ids: sorted_left,
+        next: None,
+    };
+
+    let mut score = 0;
+    for left_run in left_runs {
+        let (location_id, left_count) = left_run?;
+
+        while matches!(right_run, Some((right_id, _)) if right_id < location_id) {
+            right_run = right_runs.next().transpose()?;
+        }
+
+        if let Some((right_id, right_count)) = right_run {
+            if right_id == location_id {
+                score += location_id * left_count * right_count;
+            }
+        }
+    }
+
+    Ok(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{similarity_score, total_distance, ExternalSorter, RUN_READ_SIZE};
+    use std::num::NonZeroUsize;
+
+    #[test]
+    fn test_external_sort() {
+        let sort = |list: &[u64]| {
+            let mut sorter = ExternalSorter::new(NonZeroUsize::new(2).unwrap()).unwrap();
+            for location_id in list.iter() {
+                sorter.push(*location_id).unwrap();
+            }
+            sorter.finish().unwrap()
+        };
+
+        let left = sort(&[3, 4, 2, 1, 3, 3]);
+        let right = sort(&[4, 3, 5, 3, 9, 3]);
+
+        let merged: Vec<u64> = left.merge().unwrap().map(Result::unwrap).collect();
+        assert_eq!(vec![1, 2, 3, 3, 3, 4], merged);
+
+        assert_eq!(
+            11,
+            total_distance(left.merge().unwrap(), right.merge().unwrap()).unwrap()
+        );
+        assert_eq!(
+            31,
+            similarity_score(left.merge().unwrap(), right.merge().unwrap()).unwrap()
+        );
+
+        let empty = sort(&[]);
+        assert_eq!(
+            0,
+            similarity_score(empty.merge().unwrap(), right.merge().unwrap()).unwrap()
+        );
+
+        // Runs longer than one read, merged in reverse, still come out sorted.
+        let long: Vec<u64> = (0..RUN_READ_SIZE as u64 * 5).rev().collect();
+        let mut sorter =
+            ExternalSorter::new(NonZeroUsize::new(RUN_READ_SIZE * 2).unwrap()).unwrap();
+        for location_id in long.iter() {
+            sorter.push(*location_id).unwrap();
+        }
+        let merged: Vec<u64> = sorter
+            .finish()
+            .unwrap()
+            .merge()
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!((0..RUN_READ_SIZE as u64 * 5).collect::<Vec<u64>>(), merged);
+    }
+}
@@ -1,39 +1,382 @@
-use env_logger;
 use std::collections::HashMap;
 use std::env;
-use std::fs;
-use std::num::ParseIntError;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::mem;
+use std::num::{NonZeroUsize, ParseIntError};
+use std::process;
 
-fn load_input() -> String {
+mod external_sort;
+mod stats;
+use external_sort::{ExternalSorter, SortedRuns};
+use stats::Stats;
+
+/// Opens the file of location list pairs named on the command line, reading
+/// them from stdin instead when the path is `-`.
+fn open_input() -> Box<dyn BufRead> {
     let args: Vec<String> = env::args().collect();
-    fs::read_to_string(args.get(1).unwrap()).expect("Should have been able to read the file")
+    let path = args.get(1).unwrap();
+
+    if path == "-" {
+        return Box::new(io::stdin().lock());
+    }
+
+    let file = File::open(path).expect("Should have been able to read the file");
+    Box::new(BufReader::new(file))
 }
 
-fn main() -> Result<(), ParseIntError> {
-    env_logger::init();
+/// Which columns hold the two location lists, numbered from 1, and how the
+/// lists are compared and sorted.
+#[derive(Debug, PartialEq, Eq)]
+struct Options {
+    num_columns: usize,
+    left_column: usize,
+    right_column: usize,
+    similarity: SimilarityStrategy,
+    stats: bool,
+    /// Sort through temporary files, holding at most this many location IDs
+    /// of each list in memory.
+    external_sort: Option<NonZeroUsize>,
+}
 
-    let input = load_input();
-    let location_ids = input
-        .split('\n')
-        .filter(|line| line.len() > 0)
-        .flat_map(|line| line.split("   ").map(|val| val.parse::<u64>()))
-        .collect::<Result<Vec<u64>, ParseIntError>>()?;
+/// How the similarity score finds how often each left location ID appears in
+/// the right list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SimilarityStrategy {
+    /// Walks both sorted lists in lockstep.
+    SortedMerge,
+    /// Counts the right list into a map, so neither list needs sorting.
+    CountingMap,
+}
 
-    let mut left_list: Vec<u64> = location_ids
-        .iter()
-        .enumerate()
-        .filter(|(i, _)| i % 2 == 0)
-        .map(|(_, v)| v)
-        .copied()
-        .collect();
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            num_columns: 2,
+            left_column: 1,
+            right_column: 2,
+            similarity: SimilarityStrategy::SortedMerge,
+            stats: false,
+            external_sort: None,
+        }
+    }
+}
 
-    let mut right_list: Vec<u64> = location_ids
-        .iter()
+#[derive(Debug)]
+enum ParseOptionsError {
+    UnknownFlag(String),
+    UnknownStrategy(String),
+    MissingValue(String),
+    InvalidValue { flag: String, error: ParseIntError },
+    ColumnOutOfRange { column: usize, num_columns: usize },
+    ConflictingFlags(&'static str, &'static str),
+}
+
+impl Display for ParseOptionsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseOptionsError::UnknownFlag(flag) => {
+                f.write_fmt(format_args!("unknown flag: {}", flag))
+            }
+            ParseOptionsError::UnknownStrategy(strategy) => {
+                f.write_fmt(format_args!("unknown similarity strategy: {}", strategy))
+            }
+            ParseOptionsError::MissingValue(flag) => {
+                f.write_fmt(format_args!("missing value for {}", flag))
+            }
+            ParseOptionsError::InvalidValue { flag, error } => {
+                f.write_fmt(format_args!("invalid value for {}: {}", flag, error))
+            }
+            ParseOptionsError::ColumnOutOfRange {
+                column,
+                num_columns,
+            } => f.write_fmt(format_args!(
+                "column {} is out of range for {} columns",
+                column, num_columns
+            )),
+            ParseOptionsError::ConflictingFlags(a, b) => {
+                f.write_fmt(format_args!("{} cannot be used with {}", a, b))
+            }
+        }
+    }
+}
+
+impl Options {
+    /// Builds options from `--columns N`, `--left N` and `--right N`, which
+    /// set how many columns each line has and which two are compared, and
+    /// `--similarity merge|hash`, which picks the `SimilarityStrategy`.
+    /// `--stats` also prints a `Stats` report after the answers, and
+    /// `--external-sort N` sorts on disk in runs of `N` location IDs, where
+    /// `N` is at least 1, and always scores similarity by merging.
+    fn from_args(args: &[String]) -> Result<Options, ParseOptionsError> {
+        let mut options = Options::default();
+
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            if flag == "--stats" {
+                options.stats = true;
+                continue;
+            }
+
+            if flag == "--similarity" {
+                let strategy = args
+                    .next()
+                    .ok_or_else(|| ParseOptionsError::MissingValue(flag.clone()))?;
+
+                options.similarity = match strategy.as_str() {
+                    "merge" => SimilarityStrategy::SortedMerge,
+                    "hash" => SimilarityStrategy::CountingMap,
+                    _ => return Err(ParseOptionsError::UnknownStrategy(strategy.clone())),
+                };
+                continue;
+            }
+
+            if flag == "--external-sort" {
+                let run_size = args
+                    .next()
+                    .ok_or_else(|| ParseOptionsError::MissingValue(flag.clone()))?
+                    .parse()
+                    .map_err(|error| ParseOptionsError::InvalidValue {
+                        flag: flag.clone(),
+                        error,
+                    })?;
+                options.external_sort = Some(run_size);
+                continue;
+            }
+
+            let field = match flag.as_str() {
+                "--columns" => &mut options.num_columns,
+                "--left" => &mut options.left_column,
+                "--right" => &mut options.right_column,
+                _ => return Err(ParseOptionsError::UnknownFlag(flag.clone())),
+            };
+
+            *field = args
+                .next()
+                .ok_or_else(|| ParseOptionsError::MissingValue(flag.clone()))?
+                .parse()
+                .map_err(|error| ParseOptionsError::InvalidValue {
+                    flag: flag.clone(),
+                    error,
+                })?;
+        }
+
+        for column in [options.left_column, options.right_column] {
+            if column == 0 || column > options.num_columns {
+                return Err(ParseOptionsError::ColumnOutOfRange {
+                    column,
+                    num_columns: options.num_columns,
+                });
+            }
+        }
+
+        if options.stats && options.external_sort.is_some() {
+            return Err(ParseOptionsError::ConflictingFlags(
+                "--stats",
+                "--external-sort",
+            ));
+        }
+
+        if options.similarity == SimilarityStrategy::CountingMap && options.external_sort.is_some()
+        {
+            return Err(ParseOptionsError::ConflictingFlags(
+                "--similarity hash",
+                "--external-sort",
+            ));
+        }
+
+        Ok(options)
+    }
+}
+
+#[derive(Debug)]
+enum ReadLocationsError {
+    ReadError(io::Error),
+    SpillError(io::Error),
+    WrongNumberOfColumns {
+        line_number: usize,
+        expected: usize,
+        got: usize,
+    },
+    ParseError {
+        line_number: usize,
+        column: usize,
+        error: ParseIntError,
+    },
+}
+
+impl Display for ReadLocationsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadLocationsError::ReadError(error) => {
+                f.write_fmt(format_args!("could not read location lists: {}", error))
+            }
+            ReadLocationsError::SpillError(error) => {
+                f.write_fmt(format_args!("could not use sorted run file: {}", error))
+            }
+            ReadLocationsError::WrongNumberOfColumns {
+                line_number,
+                expected,
+                got,
+            } => f.write_fmt(format_args!(
+                "expected {} columns on line {}, got {}",
+                expected, line_number, got
+            )),
+            ReadLocationsError::ParseError {
+                line_number,
+                column,
+                error,
+            } => f.write_fmt(format_args!(
+                "could not parse location ID in column {} on line {}: {}",
+                column, line_number, error
+            )),
+        }
+    }
+}
+
+/// Reads `num_columns` whitespace separated location lists a line at a time,
+/// so only the parsed IDs are held in memory rather than the raw text.
+fn read_location_lists(
+    reader: impl BufRead,
+    num_columns: usize,
+) -> Result<Vec<Vec<u64>>, ReadLocationsError> {
+    let mut lists = vec![Vec::new(); num_columns];
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(ReadLocationsError::ReadError)?;
+        let Some(values) = parse_location_line(&line, i + 1, num_columns)? else {
+            continue;
+        };
+
+        for (list, location_id) in lists.iter_mut().zip(values) {
+            list.push(location_id);
+        }
+    }
+
+    Ok(lists)
+}
+
+/// Like `read_location_lists`, but only keeps the left and right columns and
+/// spills them to disk as sorted runs of `run_size` location IDs.
+fn read_sorted_runs(
+    reader: impl BufRead,
+    options: &Options,
+    run_size: NonZeroUsize,
+) -> Result<(SortedRuns, SortedRuns), ReadLocationsError> {
+    let mut left_sorter = ExternalSorter::new(run_size).map_err(ReadLocationsError::SpillError)?;
+    let mut right_sorter = ExternalSorter::new(run_size).map_err(ReadLocationsError::SpillError)?;
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(ReadLocationsError::ReadError)?;
+        let Some(values) = parse_location_line(&line, i + 1, options.num_columns)? else {
+            continue;
+        };
+
+        left_sorter
+            .push(values[options.left_column - 1])
+            .map_err(ReadLocationsError::SpillError)?;
+        right_sorter
+            .push(values[options.right_column - 1])
+            .map_err(ReadLocationsError::SpillError)?;
+    }
+
+    Ok((
+        left_sorter
+            .finish()
+            .map_err(ReadLocationsError::SpillError)?,
+        right_sorter
+            .finish()
+            .map_err(ReadLocationsError::SpillError)?,
+    ))
+}
+
+/// Returns `None` for blank lines.
+fn parse_location_line(
+    line: &str,
+    line_number: usize,
+    num_columns: usize,
+) -> Result<Option<Vec<u64>>, ReadLocationsError> {
+    let values: Vec<&str> = line.split_whitespace().collect();
+    if values.is_empty() {
+        return Ok(None);
+    }
+
+    if values.len() != num_columns {
+        return Err(ReadLocationsError::WrongNumberOfColumns {
+            line_number,
+            expected: num_columns,
+            got: values.len(),
+        });
+    }
+
+    values
+        .into_iter()
         .enumerate()
-        .filter(|(i, _)| i % 2 != 0)
-        .map(|(_, v)| v)
-        .copied()
-        .collect();
+        .map(|(column, value)| {
+            value
+                .parse()
+                .map_err(|error| ReadLocationsError::ParseError {
+                    line_number,
+                    column: column + 1,
+                    error,
+                })
+        })
+        .collect::<Result<Vec<u64>, ReadLocationsError>>()
+        .map(Some)
+}
+
+/// Finds both answers without ever holding a whole list in memory.
+fn run_external_sort(
+    reader: impl BufRead,
+    options: &Options,
+    run_size: NonZeroUsize,
+) -> Result<(u64, u64), ReadLocationsError> {
+    let (left_runs, right_runs) = read_sorted_runs(reader, options, run_size)?;
+
+    let total_distance = external_sort::total_distance(
+        left_runs.merge().map_err(ReadLocationsError::SpillError)?,
+        right_runs.merge().map_err(ReadLocationsError::SpillError)?,
+    )
+    .map_err(ReadLocationsError::SpillError)?;
+    let similarity_score = external_sort::similarity_score(
+        left_runs.merge().map_err(ReadLocationsError::SpillError)?,
+        right_runs.merge().map_err(ReadLocationsError::SpillError)?,
+    )
+    .map_err(ReadLocationsError::SpillError)?;
+
+    Ok((total_distance, similarity_score))
+}
+
+fn main() {
+    env_logger::init();
+
+    let args: Vec<String> = env::args().collect();
+    let options = Options::from_args(args.get(2..).unwrap_or_default()).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        eprintln!(
+            "usage: one <input|-> [--columns N] [--left N] [--right N] [--similarity merge|hash] [--stats] [--external-sort N]"
+        );
+        process::exit(1);
+    });
+
+    if let Some(run_size) = options.external_sort {
+        let (total_distance, similarity_score) =
+            run_external_sort(open_input(), &options, run_size).unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                process::exit(1);
+            });
+        println!("{}", total_distance);
+        println!("{}", similarity_score);
+        return;
+    }
+
+    let mut lists = read_location_lists(open_input(), options.num_columns).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+    let mut left_list = mem::take(&mut lists[options.left_column - 1]);
+    let mut right_list = mem::take(&mut lists[options.right_column - 1]);
 
     left_list.sort();
     right_list.sort();
@@ -46,57 +389,180 @@ fn main() -> Result<(), ParseIntError> {
 
     println!("{}", total_distance);
 
-    let occurances = build_occurances(&left_list, &right_list);
-
-    let similarity_score: u64 = left_list
-        .iter()
-        .map(|location_id| location_id * occurances.get(location_id).unwrap())
-        .sum();
+    let similarity_score = calculate_similarity_score(options.similarity, &left_list, &right_list);
 
     println!("{}", similarity_score);
 
-    Ok(())
+    if options.stats {
+        match Stats::new(&left_list, &right_list) {
+            Some(stats) => println!("{}", stats),
+            None => println!("no location pairs"),
+        }
+    }
+}
+
+fn calculate_similarity_score(
+    strategy: SimilarityStrategy,
+    sorted_left_list: &[u64],
+    sorted_right_list: &[u64],
+) -> u64 {
+    let occurances = match strategy {
+        SimilarityStrategy::SortedMerge => build_occurances(sorted_left_list, sorted_right_list),
+        SimilarityStrategy::CountingMap => count_occurances(sorted_right_list),
+    };
+
+    sorted_left_list
+        .iter()
+        .map(|location_id| location_id * occurances.get(location_id).copied().unwrap_or(0))
+        .sum()
 }
 
+/// Counts how often each left location ID appears in the right list. Both
+/// lists must be sorted, but they may have different lengths.
 fn build_occurances(sorted_left_list: &[u64], sorted_right_list: &[u64]) -> HashMap<u64, u64> {
     let mut occurances = HashMap::new();
 
-    let num_location_ids = sorted_left_list.len();
-    let mut i = 0;
     let mut j = 0;
-    let mut location_occuances = 0;
-    while i < num_location_ids && j < num_location_ids {
-        let left_location_id = sorted_left_list[i];
-        let right_location_id = sorted_right_list[j];
-
+    for (i, left_location_id) in sorted_left_list.iter().copied().enumerate() {
         // Already scanned
         if i > 0 && left_location_id == sorted_left_list[i - 1] {
-            i += 1;
-            continue;
-        }
-
-        // Store value
-        if left_location_id < right_location_id {
-            occurances.insert(left_location_id, location_occuances);
-            location_occuances = 0;
-            i += 1;
             continue;
         }
 
-        if left_location_id > right_location_id {
+        while j < sorted_right_list.len() && sorted_right_list[j] < left_location_id {
             j += 1;
-            continue;
         }
 
-        if left_location_id == right_location_id {
-            location_occuances += 1;
+        let mut location_occurances = 0;
+        while j < sorted_right_list.len() && sorted_right_list[j] == left_location_id {
+            location_occurances += 1;
             j += 1;
         }
+
+        occurances.insert(left_location_id, location_occurances);
     }
 
-    for remaining_index in i..num_location_ids {
-        occurances.insert(sorted_left_list[remaining_index], 0);
+    occurances
+}
+
+/// Counts how often each location ID appears in the right list, in any order.
+fn count_occurances(right_list: &[u64]) -> HashMap<u64, u64> {
+    let mut occurances = HashMap::new();
+
+    for location_id in right_list.iter() {
+        *occurances.entry(*location_id).or_insert(0) += 1;
     }
 
     occurances
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        calculate_similarity_score, read_location_lists, Options, ParseOptionsError,
+        ReadLocationsError, SimilarityStrategy,
+    };
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_read_location_lists() {
+        let input = "3   4\n4\t3\n\n 2 5 \n";
+        let lists = read_location_lists(input.as_bytes(), 2).unwrap();
+        assert_eq!(vec![vec![3, 4, 2], vec![4, 3, 5]], lists);
+
+        let lists = read_location_lists("1 2 3\n4 5 6\n".as_bytes(), 3).unwrap();
+        assert_eq!(vec![vec![1, 4], vec![2, 5], vec![3, 6]], lists);
+    }
+
+    #[test]
+    fn test_read_location_lists_errors() {
+        let result = read_location_lists("1 2\n3 4 5\n6 7\n".as_bytes(), 2);
+        assert!(matches!(
+            result,
+            Err(ReadLocationsError::WrongNumberOfColumns {
+                line_number: 2,
+                expected: 2,
+                got: 3
+            })
+        ));
+
+        let result = read_location_lists("1 2\n3 x\n".as_bytes(), 2);
+        assert!(matches!(
+            result,
+            Err(ReadLocationsError::ParseError {
+                line_number: 2,
+                column: 2,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_options_from_args() {
+        let args: Vec<String> = ["--columns", "3", "--right", "3"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let expected = Options {
+            num_columns: 3,
+            left_column: 1,
+            right_column: 3,
+            similarity: SimilarityStrategy::SortedMerge,
+            stats: false,
+            external_sort: None,
+        };
+        assert_eq!(expected, Options::from_args(&args).unwrap());
+
+        let args = vec!["--left".to_string(), "3".to_string()];
+        assert!(Options::from_args(&args).is_err());
+
+        let args: Vec<String> = ["--similarity", "hash", "--external-sort", "2"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        assert!(matches!(
+            Options::from_args(&args),
+            Err(ParseOptionsError::ConflictingFlags(..))
+        ));
+
+        let args = vec!["--external-sort".to_string(), "0".to_string()];
+        assert!(matches!(
+            Options::from_args(&args),
+            Err(ParseOptionsError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_similarity_score() {
+        for strategy in [
+            SimilarityStrategy::SortedMerge,
+            SimilarityStrategy::CountingMap,
+        ] {
+            let score =
+                |left: &[u64], right: &[u64]| calculate_similarity_score(strategy, left, right);
+
+            assert_eq!(31, score(&[1, 2, 3, 3, 3, 4], &[3, 3, 3, 4, 5, 9]));
+            // The last left ID matches the last right ID.
+            assert_eq!(31, score(&[3, 3, 3, 4, 5, 9], &[1, 2, 3, 3, 3, 4]));
+            // Lists of different lengths.
+            assert_eq!(4, score(&[1, 2, 3], &[2, 2, 7, 8, 9]));
+            assert_eq!(12, score(&[2, 2, 2, 5, 7], &[2, 2]));
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_strategies_agree(
+            mut left_list in prop::collection::vec(0..20u64, 0..50),
+            mut right_list in prop::collection::vec(0..20u64, 0..50),
+        ) {
+            left_list.sort();
+            right_list.sort();
+
+            prop_assert_eq!(
+                calculate_similarity_score(SimilarityStrategy::SortedMerge, &left_list, &right_list),
+                calculate_similarity_score(SimilarityStrategy::CountingMap, &left_list, &right_list)
+            );
+        }
+    }
+}
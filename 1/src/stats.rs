@@ -0,0 +1,216 @@
+use std::cmp::Ordering;
+use std::fmt::Display;
+
+const NUM_HISTOGRAM_BUCKETS: u64 = 10;
+const MAX_HISTOGRAM_BAR: usize = 40;
+const NUM_LISTED: usize = 10;
+
+/// Summary statistics over a pair of sorted location lists.
+#[derive(Debug, PartialEq)]
+pub struct Stats {
+    min_distance: u64,
+    max_distance: u64,
+    median_distance: f64,
+    /// `(bucket start, bucket end, number of pairs)`, with inclusive bounds.
+    histogram: Vec<(u64, u64, usize)>,
+    /// `(location ID, left count, right count)`, most common first.
+    shared: Vec<(u64, usize, usize)>,
+    only_left: Vec<u64>,
+    only_right: Vec<u64>,
+}
+
+impl Stats {
+    /// Returns `None` if there are no pairs to compare.
+    pub fn new(sorted_left_list: &[u64], sorted_right_list: &[u64]) -> Option<Stats> {
+        let mut distances: Vec<u64> = sorted_left_list
+            .iter()
+            .zip(sorted_right_list.iter())
+            .map(|(left, right)| left.abs_diff(*right))
+            .collect();
+        distances.sort();
+
+        let min_distance = *distances.first()?;
+        let max_distance = *distances.last()?;
+
+        let middle = distances.len() / 2;
+        let median_distance = if distances.len().is_multiple_of(2) {
+            (distances[middle - 1] as f64 + distances[middle] as f64) / 2.0
+        } else {
+            distances[middle] as f64
+        };
+
+        let (mut shared, only_left, only_right) = compare_location_ids(
+            &count_runs(sorted_left_list),
+            &count_runs(sorted_right_list),
+        );
+        shared.sort_by(|(a_id, a_left, a_right), (b_id, b_left, b_right)| {
+            (b_left + b_right)
+                .cmp(&(a_left + a_right))
+                .then(a_id.cmp(b_id))
+        });
+
+        Some(Stats {
+            min_distance,
+            max_distance,
+            median_distance,
+            histogram: build_histogram(&distances),
+            shared,
+            only_left,
+            only_right,
+        })
+    }
+}
+
+/// Collapses a sorted list into `(value, count)` runs.
+fn count_runs(sorted_list: &[u64]) -> Vec<(u64, usize)> {
+    sorted_list
+        .chunk_by(|a, b| a == b)
+        .map(|run| (run[0], run.len()))
+        .collect()
+}
+
+type Comparison = (Vec<(u64, usize, usize)>, Vec<u64>, Vec<u64>);
+
+/// Walks both runs in order, splitting location IDs into those in both
+/// lists and those in only one.
+fn compare_location_ids(left_runs: &[(u64, usize)], right_runs: &[(u64, usize)]) -> Comparison {
+    let mut shared = Vec::new();
+    let mut only_left = Vec::new();
+    let mut only_right = Vec::new();
+
+    let mut i = 0;
+    let mut j = 0;
+    while i < left_runs.len() || j < right_runs.len() {
+        let ordering = match (left_runs.get(i), right_runs.get(j)) {
+            (Some((left_id, _)), Some((right_id, _))) => left_id.cmp(right_id),
+            (Some(_), None) => Ordering::Less,
+            _ => Ordering::Greater,
+        };
+
+        match ordering {
+            Ordering::Less => {
+                only_left.push(left_runs[i].0);
+                i += 1;
+            }
+            Ordering::Greater => {
+                only_right.push(right_runs[j].0);
+                j += 1;
+            }
+            Ordering::Equal => {
+                shared.push((left_runs[i].0, left_runs[i].1, right_runs[j].1));
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+
+    (shared, only_left, only_right)
+}
+
+/// Splits sorted distances into equal width buckets from the smallest to the
+/// largest, with the last bucket ending at the largest.
+fn build_histogram(sorted_distances: &[u64]) -> Vec<(u64, u64, usize)> {
+    let min = sorted_distances[0];
+    let max = sorted_distances[sorted_distances.len() - 1];
+    let bucket_width = ((max - min) / NUM_HISTOGRAM_BUCKETS) + 1;
+
+    sorted_distances
+        .chunk_by(|a, b| (a - min) / bucket_width == (b - min) / bucket_width)
+        .map(|bucket| {
+            let start = min + (bucket[0] - min) / bucket_width * bucket_width;
+            let end = start.saturating_add(bucket_width - 1).min(max);
+            (start, end, bucket.len())
+        })
+        .collect()
+}
+
+fn list_ids(ids: &[u64]) -> String {
+    let mut listed: Vec<String> = ids.iter().take(NUM_LISTED).map(u64::to_string).collect();
+    if ids.len() > NUM_LISTED {
+        listed.push("...".to_string());
+    }
+
+    listed.join(", ")
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "distance: min {}, max {}, median {}\n",
+            self.min_distance, self.max_distance, self.median_distance
+        ))?;
+
+        f.write_str("distance histogram:\n")?;
+        let largest_bucket = self.histogram.iter().map(|(_, _, n)| *n).max().unwrap_or(0);
+        for (start, end, count) in self.histogram.iter() {
+            let bar_length = count * MAX_HISTOGRAM_BAR / largest_bucket.max(1);
+            f.write_fmt(format_args!(
+                "  {:>8}..={:<8} {:>8} {}\n",
+                start,
+                end,
+                count,
+                "#".repeat(bar_length.max(1))
+            ))?;
+        }
+
+        f.write_fmt(format_args!(
+            "most common shared location IDs ({} shared):\n",
+            self.shared.len()
+        ))?;
+        for (id, left_count, right_count) in self.shared.iter().take(NUM_LISTED) {
+            f.write_fmt(format_args!(
+                "  {:>8}: {} left, {} right\n",
+                id, left_count, right_count
+            ))?;
+        }
+
+        f.write_fmt(format_args!(
+            "only in left list ({}): {}\n",
+            self.only_left.len(),
+            list_ids(&self.only_left)
+        ))?;
+        f.write_fmt(format_args!(
+            "only in right list ({}): {}",
+            self.only_right.len(),
+            list_ids(&self.only_right)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Stats;
+
+    #[test]
+    fn test_stats() {
+        let stats = Stats::new(&[1, 2, 3, 3, 3, 4], &[3, 3, 3, 4, 5, 9]).unwrap();
+
+        assert_eq!(
+            Stats {
+                min_distance: 0,
+                max_distance: 5,
+                median_distance: 1.5,
+                histogram: vec![(0, 0, 1), (1, 1, 2), (2, 2, 2), (5, 5, 1)],
+                shared: vec![(3, 3, 3), (4, 1, 1)],
+                only_left: vec![1, 2],
+                only_right: vec![5, 9],
+            },
+            stats
+        );
+
+        // The last bucket ends at the largest distance rather than past
+        // `u64::MAX`.
+        let stats = Stats::new(&[0, 0], &[0, u64::MAX]).unwrap();
+        assert_eq!(u64::MAX, stats.max_distance);
+        assert_eq!(
+            vec![
+                (0, 1844674407370955161, 1),
+                (16602069666338596458, u64::MAX, 1)
+            ],
+            stats.histogram
+        );
+        let _ = stats.to_string();
+
+        assert_eq!(None, Stats::new(&[], &[]));
+    }
+}
@@ -8,10 +8,55 @@ pub struct Grid<T> {
     length: usize,
 }
 
+/// Where in the input a parse error occurred, with the offending line kept so
+/// it can be shown with a caret under the bad column. Lines and columns are
+/// one-based and columns count characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub line: usize,
+    pub column: usize,
+    source_line: String,
+}
+
+impl SourceLocation {
+    fn new(line_index: usize, column_index: usize, source_line: &str) -> SourceLocation {
+        SourceLocation {
+            line: line_index + 1,
+            column: column_index + 1,
+            source_line: source_line.to_string(),
+        }
+    }
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let gutter = " ".repeat(self.line.to_string().len());
+
+        f.write_fmt(format_args!(
+            "{} --> line {}, column {}\n",
+            gutter, self.line, self.column
+        ))?;
+        f.write_fmt(format_args!("{} |\n", gutter))?;
+        f.write_fmt(format_args!("{} | {}\n", self.line, self.source_line))?;
+        f.write_fmt(format_args!(
+            "{} | {}^",
+            gutter,
+            " ".repeat(self.column - 1)
+        ))
+    }
+}
+
 #[derive(Debug)]
 pub enum ParseGridError<ValError> {
-    DifferingRowSizes { expected: usize, got: usize },
-    ParseValError(ValError),
+    DifferingRowSizes {
+        expected: usize,
+        got: usize,
+        location: SourceLocation,
+    },
+    ParseValError {
+        error: ValError,
+        location: SourceLocation,
+    },
 }
 
 impl<T> Display for ParseGridError<T>
@@ -20,14 +65,18 @@ where
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ParseGridError::DifferingRowSizes { expected, got } => {
+            ParseGridError::DifferingRowSizes {
+                expected,
+                got,
+                location,
+            } => {
                 f.write_fmt(format_args!(
-                    "got differing row sizes, expected {}, got {}",
-                    expected, got,
+                    "got differing row sizes, expected {}, got {}\n{}",
+                    expected, got, location,
                 ))?;
             }
-            ParseGridError::ParseValError(v) => {
-                f.write_fmt(format_args!("ParseValError: {}", v))?;
+            ParseGridError::ParseValError { error, location } => {
+                f.write_fmt(format_args!("ParseValError: {}\n{}", error, location))?;
             }
         }
         Ok(())
@@ -44,34 +93,42 @@ where
         let mut values: Vec<T> = Vec::new();
         let mut width = None;
         let mut length = 0;
-        for line in s.split('\n').filter(|line| !line.is_empty()) {
+        for (line_index, line) in s
+            .split('\n')
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+        {
+            let num_chars = line.chars().count();
             match width {
                 Some(existing_width) => {
-                    if line.len() != existing_width {
+                    if num_chars != existing_width {
+                        let offset = existing_width.min(num_chars);
                         return Err(ParseGridError::DifferingRowSizes {
                             expected: existing_width,
-                            got: line.len(),
+                            got: num_chars,
+                            location: SourceLocation::new(line_index, offset, line),
                         });
                     }
                 }
                 None => {
-                    width = Some(line.len());
+                    width = Some(num_chars);
                 }
             }
 
-            let mut row = line
-                .chars()
-                .map(|c| T::try_from(c))
-                .collect::<Result<Vec<T>, T::Error>>()
-                .map_err(ParseGridError::ParseValError)?;
+            for (column, c) in line.chars().enumerate() {
+                let value = T::try_from(c).map_err(|error| ParseGridError::ParseValError {
+                    error,
+                    location: SourceLocation::new(line_index, column, line),
+                })?;
+                values.push(value);
+            }
 
-            values.append(&mut row);
             length += 1;
         }
 
         Ok(Grid {
             values,
-            width: width.unwrap(),
+            width: width.unwrap_or(0),
             length,
         })
     }
@@ -103,13 +160,14 @@ impl<T> Grid<T> {
         self.length
     }
 
-    pub fn iter(&self) -> GridIterator<T> {
+    pub fn iter(&self) -> GridIterator<'_, T> {
         GridIterator {
             grid: self,
             cursor: (0, 0),
         }
     }
 
+    #[allow(dead_code)]
     pub fn map_elements<U>(&self, map_function: impl Fn(((usize, usize), &T)) -> U) -> Grid<U> {
         let values = (0..self.length)
             .flat_map(|y| (0..self.width).map(move |x| (x, y)))
@@ -124,6 +182,7 @@ impl<T> Grid<T> {
         }
     }
 
+    #[allow(dead_code)]
     pub(crate) fn set(&mut self, potential_x: i32, potential_y: i32, value: T) -> Option<()> {
         let index = self.calculate_index(potential_x, potential_y)?;
 
@@ -6,6 +6,7 @@ use std::env;
 use std::fmt::Display;
 use std::fs;
 use std::hash::Hash;
+use std::process;
 
 #[derive(Debug, PartialEq, Eq)]
 struct Height(u8);
@@ -16,6 +17,15 @@ enum ParseHeightError {
     TooLarge,
 }
 
+impl Display for ParseHeightError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseHeightError::NotDigit => f.write_str("height is not a digit"),
+            ParseHeightError::TooLarge => f.write_str("height is too large"),
+        }
+    }
+}
+
 impl TryFrom<char> for Height {
     type Error = ParseHeightError;
 
@@ -111,7 +121,10 @@ fn main() {
 
     let input = load_input();
 
-    let topographic_map: Grid<Height> = input.parse().unwrap();
+    let topographic_map: Grid<Height> = input.parse().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
     log::debug!("{}", topographic_map);
 
     let trailhead_scores: Vec<OriginalScoring> = find_trailheads(&topographic_map);
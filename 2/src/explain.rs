@@ -0,0 +1,265 @@
+use std::cmp::Ordering;
+use std::fmt::Display;
+
+use crate::{
+    find_removals_in_direction, is_safe_with_dampener, Level, ParseReportError, SafetyRules,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExplainFormat {
+    Table,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reason {
+    DirectionChange,
+    EqualLevels,
+    StepTooSmall,
+    StepTooLarge,
+}
+
+impl Reason {
+    fn as_json(&self) -> &'static str {
+        match self {
+            Reason::DirectionChange => "direction_change",
+            Reason::EqualLevels => "equal_levels",
+            Reason::StepTooSmall => "step_too_small",
+            Reason::StepTooLarge => "step_too_large",
+        }
+    }
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            Reason::DirectionChange => "direction change",
+            Reason::EqualLevels => "equal levels",
+            Reason::StepTooSmall => "step too small",
+            Reason::StepTooLarge => "step too large",
+        };
+        f.write_str(reason)
+    }
+}
+
+/// Why a report is or isn't safe. Level indexes are zero-based.
+#[derive(Debug, PartialEq, Eq)]
+struct Explanation {
+    report_number: usize,
+    safe: bool,
+    /// The first level that breaks the rules when every level is kept.
+    first_violation: Option<(usize, Reason)>,
+    /// The fewest levels to remove for the report to be safe with the
+    /// dampener, if it can be.
+    dampened_removals: Option<Vec<usize>>,
+}
+
+pub fn print_explanations(
+    reports: impl Iterator<Item = Result<Vec<Level>, ParseReportError>>,
+    rules: &SafetyRules,
+    format: ExplainFormat,
+) -> Result<(), ParseReportError> {
+    if format == ExplainFormat::Table {
+        println!(
+            "{:>6} | {:<6} | {:<24} | dampened",
+            "report", "safe", "first violation"
+        );
+    }
+
+    for (i, report) in reports.enumerate() {
+        let explanation = explain(i + 1, &report?, rules);
+
+        match format {
+            ExplainFormat::Table => print_table_row(&explanation),
+            ExplainFormat::Json => print_json_line(&explanation),
+        }
+    }
+
+    Ok(())
+}
+
+fn explain(report_number: usize, report: &[Level], rules: &SafetyRules) -> Explanation {
+    let dampened_rules = rules.dampened().expect("checked when parsing options");
+
+    Explanation {
+        report_number,
+        safe: is_safe_with_dampener(report, rules),
+        first_violation: find_first_violation(report, rules),
+        dampened_removals: find_removals(report, &dampened_rules),
+    }
+}
+
+fn find_first_violation(report: &[Level], rules: &SafetyRules) -> Option<(usize, Reason)> {
+    // The report's direction is set by its first change in level.
+    let direction = if rules.require_monotonic {
+        report
+            .windows(2)
+            .map(|pair| pair[0].cmp(&pair[1]))
+            .find(|ordering| *ordering != Ordering::Equal)
+    } else {
+        None
+    };
+
+    report.windows(2).enumerate().find_map(|(i, pair)| {
+        let (a, b) = (pair[0], pair[1]);
+        if rules.is_step_safe(a, b, direction) {
+            return None;
+        }
+
+        let reason = if a == b {
+            Reason::EqualLevels
+        } else if direction.is_some_and(|ordering| a.cmp(&b) != ordering) {
+            Reason::DirectionChange
+        } else if a.abs_diff(b) < *rules.steps.start() {
+            Reason::StepTooSmall
+        } else {
+            Reason::StepTooLarge
+        };
+
+        Some((i + 1, reason))
+    })
+}
+
+/// Finds the fewest levels to remove for `report` to be safe, preferring
+/// to remove earlier levels when there is a choice.
+fn find_removals(report: &[Level], rules: &SafetyRules) -> Option<Vec<usize>> {
+    rules
+        .directions()
+        .into_iter()
+        .filter_map(|direction| find_removals_in_direction(report, direction, rules))
+        .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+}
+
+fn print_table_row(explanation: &Explanation) {
+    let first_violation = match explanation.first_violation {
+        Some((index, reason)) => format!("{}: {}", index, reason),
+        None => String::new(),
+    };
+
+    let dampened = match &explanation.dampened_removals {
+        Some(removals) if removals.is_empty() => "safe".to_string(),
+        Some(removals) => format!("safe, removed {}", join(removals, ", ")),
+        None => "unsafe".to_string(),
+    };
+
+    println!(
+        "{:>6} | {:<6} | {:<24} | {}",
+        explanation.report_number,
+        if explanation.safe { "yes" } else { "no" },
+        first_violation,
+        dampened,
+    );
+}
+
+fn print_json_line(explanation: &Explanation) {
+    let first_violation = match explanation.first_violation {
+        Some((index, reason)) => {
+            format!(r#"{{"index":{},"reason":"{}"}}"#, index, reason.as_json())
+        }
+        None => "null".to_string(),
+    };
+
+    let removed = match &explanation.dampened_removals {
+        Some(removals) => format!("[{}]", join(removals, ",")),
+        None => "null".to_string(),
+    };
+
+    println!(
+        r#"{{"report":{},"safe":{},"violation":{},"dampened_safe":{},"removed":{}}}"#,
+        explanation.report_number,
+        explanation.safe,
+        first_violation,
+        explanation.dampened_removals.is_some(),
+        removed,
+    );
+}
+
+fn join(values: &[usize], separator: &str) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{explain, find_removals, Explanation, Reason};
+    use crate::{is_safe, is_safe_with_dampener, Level, SafetyRules};
+    use itertools::Itertools;
+
+    #[test]
+    fn test_explain_example() {
+        let rules = SafetyRules::default();
+
+        assert_eq!(
+            Explanation {
+                report_number: 1,
+                safe: true,
+                first_violation: None,
+                dampened_removals: Some(vec![]),
+            },
+            explain(1, &[7, 6, 4, 2, 1], &rules)
+        );
+        assert_eq!(
+            Explanation {
+                report_number: 2,
+                safe: false,
+                first_violation: Some((2, Reason::StepTooLarge)),
+                dampened_removals: None,
+            },
+            explain(2, &[1, 2, 7, 8, 9], &rules)
+        );
+        assert_eq!(
+            Explanation {
+                report_number: 4,
+                safe: false,
+                first_violation: Some((2, Reason::DirectionChange)),
+                dampened_removals: Some(vec![1]),
+            },
+            explain(4, &[1, 3, 2, 4, 5], &rules)
+        );
+        assert_eq!(
+            Explanation {
+                report_number: 5,
+                safe: false,
+                first_violation: Some((3, Reason::EqualLevels)),
+                dampened_removals: Some(vec![2]),
+            },
+            explain(5, &[8, 6, 4, 4, 1], &rules)
+        );
+    }
+
+    #[test]
+    fn test_removals_are_fewest() {
+        // Every report of length 1..=6 over levels 1..=4.
+        for length in 1..=6 {
+            for report in (0..length).map(|_| 1..=4).multi_cartesian_product() {
+                for max_bad_levels in 0..=3 {
+                    let rules = SafetyRules {
+                        max_bad_levels,
+                        ..SafetyRules::default()
+                    };
+
+                    let fewest = (0..=max_bad_levels).find(|max_bad_levels| {
+                        let rules = SafetyRules {
+                            max_bad_levels: *max_bad_levels,
+                            ..rules.clone()
+                        };
+                        is_safe_with_dampener(&report, &rules)
+                    });
+                    let removals = find_removals(&report, &rules);
+                    assert_eq!(fewest, removals.as_ref().map(|removals| removals.len()));
+
+                    if let Some(removals) = removals {
+                        let kept: Vec<Level> = (0..report.len())
+                            .filter(|i| !removals.contains(i))
+                            .map(|i| report[i])
+                            .collect();
+                        assert!(is_safe(&kept, &rules), "{:?} less {:?}", report, removals);
+                    }
+                }
+            }
+        }
+    }
+}
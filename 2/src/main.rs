@@ -1,88 +1,622 @@
 use std::cmp::Ordering;
 use std::env;
-use std::fs;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
 use std::num::ParseIntError;
+use std::ops::RangeInclusive;
+use std::process;
+use std::str::FromStr;
 
-use itertools::Itertools;
+mod explain;
+use explain::{print_explanations, ExplainFormat};
 
-fn load_input() -> String {
+/// Opens the reports file, or stdin when the path is `-`, so reports can be
+/// piped in one per line.
+fn open_input() -> Box<dyn BufRead> {
     let args: Vec<String> = env::args().collect();
-    fs::read_to_string(args.get(1).unwrap()).expect("Should have been able to read the file")
+    let path = args.get(1).unwrap();
+
+    if path == "-" {
+        return Box::new(io::stdin().lock());
+    }
+
+    let file = File::open(path).expect("Should have been able to read the file");
+    Box::new(BufReader::new(file))
+}
+
+fn exit_with_error(err: impl Display) -> ! {
+    eprintln!("{}", err);
+    process::exit(1);
+}
+
+/// What makes a report safe. The defaults are the puzzle's rules: levels
+/// strictly increase or decrease by 1 to 3 with no bad levels tolerated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SafetyRules {
+    steps: RangeInclusive<u128>,
+    allow_equal: bool,
+    require_monotonic: bool,
+    max_bad_levels: usize,
+}
+
+impl Default for SafetyRules {
+    fn default() -> Self {
+        SafetyRules {
+            steps: 1..=3,
+            allow_equal: false,
+            require_monotonic: true,
+            max_bad_levels: 0,
+        }
+    }
+}
+
+/// The rules reports are checked against, and whether each report is
+/// explained instead of only counting the safe ones.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Options {
+    rules: SafetyRules,
+    explain: Option<ExplainFormat>,
+}
+
+#[derive(Debug)]
+enum ParseOptionsError {
+    UnknownFlag(String),
+    UnknownFormat(String),
+    MissingValue(String),
+    InvalidValue { flag: String, error: ParseIntError },
+    EmptyStepRange { min: u128, max: u128 },
+    ToleranceTooLarge(usize),
+}
+
+impl Display for ParseOptionsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseOptionsError::UnknownFlag(flag) => {
+                f.write_fmt(format_args!("unknown flag: {}", flag))
+            }
+            ParseOptionsError::UnknownFormat(format) => {
+                f.write_fmt(format_args!("unknown explain format: {}", format))
+            }
+            ParseOptionsError::MissingValue(flag) => {
+                f.write_fmt(format_args!("missing value for {}", flag))
+            }
+            ParseOptionsError::InvalidValue { flag, error } => {
+                f.write_fmt(format_args!("invalid value for {}: {}", flag, error))
+            }
+            ParseOptionsError::EmptyStepRange { min, max } => f.write_fmt(format_args!(
+                "--min-step {} is greater than --max-step {}",
+                min, max
+            )),
+            ParseOptionsError::ToleranceTooLarge(tolerance) => f.write_fmt(format_args!(
+                "--tolerate {} leaves no room for the dampener's extra level",
+                tolerance
+            )),
+        }
+    }
+}
+
+impl Options {
+    /// Builds options from command line flags, starting from the default
+    /// rules:
+    ///
+    /// `--min-step N`, `--max-step N`, `--allow-equal`, `--any-direction`
+    /// and `--tolerate N` (the number of bad levels that may be removed)
+    /// change the rules, and `--explain table|json` prints why each report
+    /// is or isn't safe instead of the counts.
+    fn from_args(args: &[String]) -> Result<Options, ParseOptionsError> {
+        let mut options = Options::default();
+        let rules = &mut options.rules;
+
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            match flag.as_str() {
+                "--allow-equal" => rules.allow_equal = true,
+                "--any-direction" => rules.require_monotonic = false,
+                "--explain" => {
+                    let format = args
+                        .next()
+                        .ok_or_else(|| ParseOptionsError::MissingValue(flag.clone()))?;
+
+                    options.explain = Some(match format.as_str() {
+                        "table" => ExplainFormat::Table,
+                        "json" => ExplainFormat::Json,
+                        _ => return Err(ParseOptionsError::UnknownFormat(format.clone())),
+                    });
+                }
+                "--min-step" => {
+                    let value = parse_value(flag, args.next())?;
+                    rules.steps = value..=*rules.steps.end();
+                }
+                "--max-step" => {
+                    let value = parse_value(flag, args.next())?;
+                    rules.steps = *rules.steps.start()..=value;
+                }
+                "--tolerate" => {
+                    rules.max_bad_levels = parse_value(flag, args.next())?;
+                }
+                _ => return Err(ParseOptionsError::UnknownFlag(flag.clone())),
+            }
+        }
+
+        if rules.steps.is_empty() {
+            return Err(ParseOptionsError::EmptyStepRange {
+                min: *rules.steps.start(),
+                max: *rules.steps.end(),
+            });
+        }
+
+        if rules.dampened().is_none() {
+            return Err(ParseOptionsError::ToleranceTooLarge(rules.max_bad_levels));
+        }
+
+        Ok(options)
+    }
+}
+
+fn parse_value<T: FromStr<Err = ParseIntError>>(
+    flag: &str,
+    value: Option<&String>,
+) -> Result<T, ParseOptionsError> {
+    value
+        .ok_or_else(|| ParseOptionsError::MissingValue(flag.to_string()))?
+        .parse()
+        .map_err(|error| ParseOptionsError::InvalidValue {
+            flag: flag.to_string(),
+            error,
+        })
 }
 
-fn main() -> Result<(), ParseIntError> {
+impl SafetyRules {
+    /// These rules with the Problem Dampener tolerating one more bad level,
+    /// or `None` if that count doesn't fit in a `usize`.
+    fn dampened(&self) -> Option<SafetyRules> {
+        Some(SafetyRules {
+            max_bad_levels: self.max_bad_levels.checked_add(1)?,
+            ..self.clone()
+        })
+    }
+
+    /// The directions a report may run in, or `None` when it may change
+    /// direction freely.
+    fn directions(&self) -> Vec<Option<Ordering>> {
+        if self.require_monotonic {
+            vec![Some(Ordering::Less), Some(Ordering::Greater)]
+        } else {
+            vec![None]
+        }
+    }
+
+    fn is_step_safe(&self, a: Level, b: Level, direction: Option<Ordering>) -> bool {
+        if a == b {
+            return self.allow_equal;
+        }
+
+        if let Some(ordering) = direction {
+            if a.cmp(&b) != ordering {
+                return false;
+            }
+        }
+
+        self.steps.contains(&a.abs_diff(b))
+    }
+}
+
+fn main() {
     env_logger::init();
 
-    let input = load_input();
+    let args: Vec<String> = env::args().collect();
+    let Options { rules, explain } = Options::from_args(args.get(2..).unwrap_or_default())
+        .unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            eprintln!(
+                "usage: two <input|-> [--min-step N] [--max-step N] [--allow-equal] [--any-direction] [--tolerate N] [--explain table|json]"
+            );
+            process::exit(1);
+        });
+    log::debug!("rules: {:?}", rules);
 
-    let reports = input
-        .split('\n')
-        .filter(|line| !line.is_empty())
-        .map(|line| line.split(" ").map(|level| level.parse()).collect())
-        .collect::<Result<Vec<Vec<u64>>, ParseIntError>>()?;
+    let reports = read_reports(open_input());
 
-    log::debug!("reports: {:?}", reports);
+    if let Some(format) = explain {
+        print_explanations(reports, &rules, format).unwrap_or_else(|err| exit_with_error(err));
+        return;
+    }
 
-    let num_reports_safe: u64 = reports
-        .iter()
-        .fold(0, |acc, report| if is_safe(report) { acc + 1 } else { acc });
+    let dampened_rules = rules.dampened().expect("checked when parsing options");
+
+    // Reports are counted as they are read so only one is held at a time.
+    let mut num_reports_safe: u64 = 0;
+    let mut num_reports_safe_with_dampener: u64 = 0;
+    for report in reports {
+        let report = report.unwrap_or_else(|err| exit_with_error(err));
+        log::debug!("report: {:?}", report);
+
+        if is_safe_with_dampener(&report, &rules) {
+            num_reports_safe += 1;
+        }
+
+        if is_safe_with_dampener(&report, &dampened_rules) {
+            num_reports_safe_with_dampener += 1;
+        }
+    }
 
     println!("{}", num_reports_safe);
+    println!("{}", num_reports_safe_with_dampener);
+}
 
-    let num_reports_safe_with_dampener = reports.iter().fold(0, |acc, report| {
-        if report
-            .iter()
-            .combinations(report.len() - 1)
-            .any(|report| is_safe(&report.into_iter().copied().collect::<Vec<u64>>()))
+/// Levels are signed and wide enough for any `u64` or `i64` reading, so
+/// differences between them can't overflow.
+type Level = i128;
+
+#[derive(Debug)]
+enum ParseReportError {
+    ReadError(io::Error),
+    ParseLevelError {
+        line_number: usize,
+        level: String,
+        error: ParseIntError,
+    },
+}
+
+impl Display for ParseReportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseReportError::ReadError(error) => {
+                f.write_fmt(format_args!("could not read reports: {}", error))
+            }
+            ParseReportError::ParseLevelError {
+                line_number,
+                level,
+                error,
+            } => f.write_fmt(format_args!(
+                "could not parse level {:?} on line {}: {}",
+                level, line_number, error
+            )),
+        }
+    }
+}
+
+/// Lazily reads one report per non-empty line from `reader`.
+fn read_reports(
+    reader: impl BufRead,
+) -> impl Iterator<Item = Result<Vec<Level>, ParseReportError>> {
+    reader
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| match line {
+            Ok(line) if line.is_empty() => None,
+            Ok(line) => Some(parse_report(i + 1, &line)),
+            Err(error) => Some(Err(ParseReportError::ReadError(error))),
+        })
+}
+
+fn parse_report(line_number: usize, line: &str) -> Result<Vec<Level>, ParseReportError> {
+    line.split(" ")
+        .map(|level| {
+            level
+                .parse()
+                .map_err(|error| ParseReportError::ParseLevelError {
+                    line_number,
+                    level: level.to_string(),
+                    error,
+                })
+        })
+        .collect()
+}
+
+fn is_safe(report: &[Level], rules: &SafetyRules) -> bool {
+    rules.directions().into_iter().any(|direction| {
+        report
+            .windows(2)
+            .all(|pair| rules.is_step_safe(pair[0], pair[1], direction))
+    })
+}
+
+/// Whether `report` can be made safe by removing at most
+/// `rules.max_bad_levels` levels. Runs in a single pass over the report,
+/// taking O(n * k^2) time and O(k^2) space for `k` removals.
+fn is_safe_with_dampener(report: &[Level], rules: &SafetyRules) -> bool {
+    if rules.max_bad_levels == 0 {
+        return is_safe(report, rules);
+    }
+
+    rules
+        .directions()
+        .into_iter()
+        .any(|direction| can_be_made_safe(report, direction, rules))
+}
+
+fn can_be_made_safe(report: &[Level], direction: Option<Ordering>, rules: &SafetyRules) -> bool {
+    let max_removals = rules.max_bad_levels;
+    let num_levels = report.len();
+    if num_levels.saturating_sub(1) <= max_removals {
+        return true;
+    }
+
+    // `reachable[i % window][j]` is whether a safe run can end by keeping
+    // level `i` having removed exactly `j` of the levels before it. A kept
+    // level can only follow one of the previous `max_removals + 1` levels, so
+    // older rows are overwritten.
+    let window = max_removals + 1;
+    let mut reachable = vec![vec![false; window]; window];
+
+    for i in 0..num_levels {
+        let mut row = vec![false; window];
+        fill_reachable_row(report, direction, rules, i, &mut row, |previous| {
+            &reachable[previous % window]
+        });
+
+        let levels_after = num_levels - i - 1;
+        if levels_after <= max_removals
+            && row
+                .iter()
+                .enumerate()
+                .any(|(removed, reachable)| *reachable && removed + levels_after <= max_removals)
         {
-            acc + 1
-        } else {
-            acc
+            return true;
         }
-    });
 
-    println!("{}", num_reports_safe_with_dampener);
+        reachable[i % window] = row;
+    }
 
-    Ok(())
+    false
 }
 
-fn is_safe(report: &[u64]) -> bool {
-    let orderings: Vec<Ordering> = report
-        .iter()
-        .zip(report.iter().skip(1))
-        .map(|(a, b)| a.cmp(b))
-        .collect();
+/// Fills in row `i` of the `reachable` table described in
+/// `can_be_made_safe`, looking up the rows of earlier levels with `earlier`.
+fn fill_reachable_row<'a>(
+    report: &[Level],
+    direction: Option<Ordering>,
+    rules: &SafetyRules,
+    i: usize,
+    row: &mut [bool],
+    earlier: impl Fn(usize) -> &'a [bool],
+) {
+    for (removed, reachable_with_removed) in row.iter_mut().enumerate() {
+        // Every level before this one was removed.
+        if removed >= i {
+            *reachable_with_removed = removed == i;
+            continue;
+        }
 
-    log::debug!("{:?}", orderings);
+        // Keep the level `skipped + 1` back, removing the `skipped` levels in
+        // between.
+        *reachable_with_removed =
+            find_previous_kept(report, direction, rules, i, removed, &earlier).is_some();
+    }
+}
 
-    let all_decreasing = orderings
-        .iter()
-        .copied()
-        .all(|ordering| ordering == Ordering::Greater);
-    let all_increasing = orderings
-        .iter()
-        .copied()
-        .all(|ordering| ordering == Ordering::Less);
+/// Returns how many levels were skipped to reach a kept level `i` with
+/// `removed` levels removed before it, fewest first, or `None` if it can't
+/// be reached.
+fn find_previous_kept<'a>(
+    report: &[Level],
+    direction: Option<Ordering>,
+    rules: &SafetyRules,
+    i: usize,
+    removed: usize,
+    earlier: impl Fn(usize) -> &'a [bool],
+) -> Option<usize> {
+    (0..=removed).find(|skipped| {
+        let previous = i - skipped - 1;
+        earlier(previous)[removed - skipped]
+            && rules.is_step_safe(report[previous], report[i], direction)
+    })
+}
 
-    if !all_increasing && !all_decreasing {
-        return false;
+/// Finds the fewest levels to remove for `report` to be safe in
+/// `direction`, or `None` if it takes more than `rules.max_bad_levels`.
+/// Builds the same table as `can_be_made_safe` but keeps every row, taking
+/// O(n * k) space, and walks back through it from the last kept level.
+/// Where there is a choice, later levels are kept and earlier ones removed.
+fn find_removals_in_direction(
+    report: &[Level],
+    direction: Option<Ordering>,
+    rules: &SafetyRules,
+) -> Option<Vec<usize>> {
+    let num_levels = report.len();
+    if num_levels == 0 {
+        return Some(Vec::new());
     }
 
-    let differences: Vec<u64> = if all_increasing {
-        report
+    // No more than every level before the last can be removed before it.
+    let width = rules.max_bad_levels.min(num_levels - 1) + 1;
+    let mut reachable: Vec<Vec<bool>> = Vec::with_capacity(num_levels);
+    for i in 0..num_levels {
+        let mut row = vec![false; width];
+        fill_reachable_row(report, direction, rules, i, &mut row, |previous| {
+            &reachable[previous]
+        });
+        reachable.push(row);
+    }
+
+    let total_removed = |(i, removed): &(usize, usize)| removed + num_levels - i - 1;
+    let (mut i, mut removed) = (0..num_levels)
+        .rev()
+        .flat_map(|i| (0..width).map(move |removed| (i, removed)))
+        .filter(|(i, removed)| reachable[*i][*removed])
+        .min_by_key(total_removed)
+        .filter(|end| total_removed(end) <= rules.max_bad_levels)?;
+
+    let mut removals: Vec<usize> = (i + 1..num_levels).collect();
+    while removed < i {
+        let skipped = find_previous_kept(report, direction, rules, i, removed, |previous| {
+            &reachable[previous]
+        })
+        .expect("a reachable level has a reachable previous level");
+
+        removals.extend(i - skipped..i);
+        i -= skipped + 1;
+        removed -= skipped;
+    }
+    removals.extend(0..i);
+    removals.sort();
+
+    Some(removals)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        is_safe, is_safe_with_dampener, read_reports, ExplainFormat, Level, Options,
+        ParseReportError, SafetyRules,
+    };
+    use itertools::Itertools;
+
+    const EXAMPLE: [&[Level]; 6] = [
+        &[7, 6, 4, 2, 1],
+        &[1, 2, 7, 8, 9],
+        &[9, 7, 6, 2, 1],
+        &[1, 3, 2, 4, 5],
+        &[8, 6, 4, 4, 1],
+        &[1, 3, 6, 7, 9],
+    ];
+
+    fn tolerating(max_bad_levels: usize) -> SafetyRules {
+        SafetyRules {
+            max_bad_levels,
+            ..SafetyRules::default()
+        }
+    }
+
+    fn is_safe_with_dampener_brute_force(report: &[Level], rules: &SafetyRules) -> bool {
+        (0..=rules.max_bad_levels.min(report.len())).any(|removals| {
+            report
+                .iter()
+                .copied()
+                .combinations(report.len() - removals)
+                .any(|report| is_safe(&report, rules))
+        })
+    }
+
+    #[test]
+    fn test_example() {
+        let safe: Vec<bool> = EXAMPLE
             .iter()
-            .zip(report.iter().skip(1))
-            .map(|(a, b)| b - a)
-            .collect()
-    } else {
-        report
+            .map(|report| is_safe_with_dampener(report, &tolerating(0)))
+            .collect();
+        assert_eq!(vec![true, false, false, false, false, true], safe);
+
+        let safe: Vec<bool> = EXAMPLE
             .iter()
-            .zip(report.iter().skip(1))
-            .map(|(a, b)| a - b)
-            .collect()
-    };
+            .map(|report| is_safe_with_dampener(report, &tolerating(1)))
+            .collect();
+        assert_eq!(vec![true, false, false, true, true, true], safe);
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let variants = [
+            SafetyRules::default(),
+            SafetyRules {
+                allow_equal: true,
+                ..SafetyRules::default()
+            },
+            SafetyRules {
+                require_monotonic: false,
+                steps: 2..=2,
+                ..SafetyRules::default()
+            },
+        ];
 
-    log::debug!("{:?}", differences);
+        // Every report of length 1..=6 over levels 1..=4.
+        for length in 1..=6 {
+            for report in (0..length).map(|_| 1..=4).multi_cartesian_product() {
+                for (variant, max_bad_levels) in variants.iter().cartesian_product(0..=3) {
+                    let rules = SafetyRules {
+                        max_bad_levels,
+                        ..variant.clone()
+                    };
+                    assert_eq!(
+                        is_safe_with_dampener_brute_force(&report, &rules),
+                        is_safe_with_dampener(&report, &rules),
+                        "{:?} with {:?}",
+                        report,
+                        rules,
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_long_report() {
+        let mut report: Vec<Level> = (0..100_000).collect();
+        assert!(is_safe_with_dampener(&report, &tolerating(0)));
+
+        report[50_000] = 0;
+        assert!(!is_safe_with_dampener(&report, &tolerating(0)));
+        assert!(is_safe_with_dampener(&report, &tolerating(1)));
+    }
+
+    #[test]
+    fn test_options_from_args() {
+        let args: Vec<String> = [
+            "--max-step",
+            "5",
+            "--allow-equal",
+            "--tolerate",
+            "2",
+            "--explain",
+            "json",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
 
-    differences.into_iter().all(|diff| (1..=3).contains(&diff))
+        let expected = Options {
+            rules: SafetyRules {
+                steps: 1..=5,
+                allow_equal: true,
+                require_monotonic: true,
+                max_bad_levels: 2,
+            },
+            explain: Some(ExplainFormat::Json),
+        };
+        assert_eq!(expected, Options::from_args(&args).unwrap());
+
+        assert!(Options::from_args(&["--min-step".to_string()]).is_err());
+        assert!(Options::from_args(&["--bogus".to_string()]).is_err());
+
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert!(Options::from_args(&args(&["--min-step", "5", "--max-step", "2"])).is_err());
+        assert!(Options::from_args(&args(&["--tolerate", "18446744073709551615"])).is_err());
+        assert!(Options::from_args(&args(&["--tolerate", "18446744073709551616"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_reports() {
+        let input = "-3 -1 2\n\n18446744073709551615 18446744073709551614\n";
+        let reports = read_reports(input.as_bytes())
+            .collect::<Result<Vec<Vec<Level>>, ParseReportError>>()
+            .unwrap();
+        assert_eq!(
+            vec![
+                vec![-3, -1, 2],
+                vec![u64::MAX as Level, u64::MAX as Level - 1]
+            ],
+            reports
+        );
+        assert!(is_safe(&reports[0], &SafetyRules::default()));
+        assert!(is_safe(&reports[1], &SafetyRules::default()));
+
+        let result = read_reports("1 2 3\n4 five 6\n".as_bytes())
+            .collect::<Result<Vec<Vec<Level>>, ParseReportError>>();
+        assert!(matches!(
+            result,
+            Err(ParseReportError::ParseLevelError { line_number: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_extreme_differences() {
+        let report = [i64::MIN as Level, i64::MAX as Level];
+        let rules = SafetyRules {
+            steps: 1..=u128::MAX,
+            ..SafetyRules::default()
+        };
+        assert!(is_safe(&report, &rules));
+        assert!(!is_safe(&report, &SafetyRules::default()));
+    }
 }
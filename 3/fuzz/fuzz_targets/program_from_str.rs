@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// The binary's own `main` goes unused here.
+#[allow(dead_code)]
+#[path = "../../src/main.rs"]
+mod three;
+
+// Parsing must return `Ok` or `Err` for any input, never panic, and so must
+// disassembling whatever was parsed.
+fuzz_target!(|s: &str| {
+    if let Ok(program) = s.parse::<three::Program>() {
+        let _ = program.to_string();
+    }
+});
@@ -1,8 +1,16 @@
+use std::any;
+use std::collections::VecDeque;
 use std::env;
-use std::fs;
-use std::num::TryFromIntError;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::ops::Range;
+use std::process;
 use std::str::FromStr;
 
+/// Byte offsets into the program's source.
+type Span = Range<usize>;
+
 #[derive(Debug, PartialEq, Eq)]
 enum Instruction {
     Mul(u32, u32),
@@ -10,57 +18,203 @@ enum Instruction {
     Dont,
 }
 
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Instruction::Mul(a, b) => f.write_fmt(format_args!("mul({},{})", a, b)),
+            Instruction::Do => f.write_str("do()"),
+            Instruction::Dont => f.write_str("don't()"),
+        }
+    }
+}
+
+/// A whole program parsed up front, for when every instruction is needed at
+/// once. Running the program streams instructions instead.
 #[derive(Debug)]
-struct Program {
-    instructions: Vec<Instruction>,
+pub(crate) struct Program {
+    instructions: Vec<(Instruction, Span)>,
 }
 
-struct Interpreter1 {}
+impl Program {
+    fn from_reader(reader: impl BufRead) -> Result<Program, ParseProgramError> {
+        let instructions = parse_instructions(reader).collect::<Result<_, _>>()?;
 
-impl Interpreter1 {
-    fn run(&self, program: &Program) -> u32 {
-        program
-            .instructions
-            .iter()
-            .map(|instruction| match instruction {
-                Instruction::Mul(a, b) => a * b,
-                Instruction::Do => 0,
-                Instruction::Dont => 0,
-            })
-            .sum()
+        Ok(Program { instructions })
     }
 }
 
-struct Interpreter2 {
-    instructions_enabled: bool,
+/// Disassembles the program one instruction per line, with its source offsets
+/// and, following `Interpreter2`, whether each `mul` is enabled and the
+/// running total.
+impl Display for Program {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{:>8}  {:<16}  {:<16}  {:<8}  total",
+            "index", "offsets", "instruction", "status"
+        ))?;
+
+        let mut interpreter = Interpreter2::<u128>::new();
+        for (i, (instruction, span)) in self.instructions.iter().enumerate() {
+            if let Err(err) = interpreter.execute(i, instruction, span) {
+                return f.write_fmt(format_args!("\n{}", err));
+            }
+
+            let status = match instruction {
+                Instruction::Mul(_, _) if interpreter.instructions_enabled => "enabled",
+                Instruction::Mul(_, _) => "disabled",
+                _ => "",
+            };
+
+            f.write_fmt(format_args!(
+                "\n{:>8}  {:<16}  {:<16}  {:<8}  {}",
+                i,
+                format!("{}..{}", span.start, span.end),
+                instruction.to_string(),
+                status,
+                interpreter.total
+            ))?;
+        }
+
+        Ok(())
+    }
 }
 
-impl Interpreter2 {
-    fn run(&mut self, program: &Program) -> u32 {
-        let mut total = 0;
+/// An unsigned integer the interpreters can total into.
+trait Accumulator: Copy + Default + Display + From<u32> {
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+}
 
-        for instruction in program.instructions.iter() {
-            match instruction {
-                Instruction::Mul(a, b) => {
-                    if self.instructions_enabled {
-                        total += a * b;
-                    }
+macro_rules! impl_accumulator {
+    ($($t:ty),*) => {
+        $(
+            impl Accumulator for $t {
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_add(self, rhs)
                 }
-                Instruction::Do => {
-                    self.instructions_enabled = true;
+
+                fn checked_mul(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_mul(self, rhs)
                 }
-                Instruction::Dont => {
-                    self.instructions_enabled = false;
+            }
+        )*
+    };
+}
+
+impl_accumulator!(u32, u64, u128);
+
+#[derive(Debug, PartialEq, Eq)]
+struct OverflowError {
+    result_type: &'static str,
+    instruction_index: usize,
+    span: Span,
+}
+
+impl Display for OverflowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "total overflowed {} at instruction {} (characters {}..{})",
+            self.result_type, self.instruction_index, self.span.start, self.span.end
+        ))
+    }
+}
+
+/// Adds `a * b` to `total`, or reports the instruction that would overflow.
+fn accumulate<T: Accumulator>(
+    total: T,
+    a: u32,
+    b: u32,
+    instruction_index: usize,
+    span: &Span,
+) -> Result<T, OverflowError> {
+    T::from(a)
+        .checked_mul(T::from(b))
+        .and_then(|product| total.checked_add(product))
+        .ok_or_else(|| OverflowError {
+            result_type: any::type_name::<T>(),
+            instruction_index,
+            span: span.clone(),
+        })
+}
+
+/// Interpreters are fed one instruction at a time, so they can run straight
+/// off a stream of instructions without holding the whole program.
+struct Interpreter1<T> {
+    total: T,
+}
+
+impl<T: Accumulator> Interpreter1<T> {
+    fn new() -> Self {
+        Interpreter1 {
+            total: T::default(),
+        }
+    }
+
+    fn execute(
+        &mut self,
+        instruction_index: usize,
+        instruction: &Instruction,
+        span: &Span,
+    ) -> Result<(), OverflowError> {
+        if let Instruction::Mul(a, b) = instruction {
+            self.total = accumulate(self.total, *a, *b, instruction_index, span)?;
+        }
+
+        Ok(())
+    }
+}
+
+struct Interpreter2<T> {
+    instructions_enabled: bool,
+    total: T,
+}
+
+impl<T: Accumulator> Interpreter2<T> {
+    fn new() -> Self {
+        Interpreter2 {
+            instructions_enabled: true,
+            total: T::default(),
+        }
+    }
+
+    fn execute(
+        &mut self,
+        instruction_index: usize,
+        instruction: &Instruction,
+        span: &Span,
+    ) -> Result<(), OverflowError> {
+        match instruction {
+            Instruction::Mul(a, b) => {
+                if self.instructions_enabled {
+                    self.total = accumulate(self.total, *a, *b, instruction_index, span)?;
                 }
             }
+            Instruction::Do => {
+                self.instructions_enabled = true;
+            }
+            Instruction::Dont => {
+                self.instructions_enabled = false;
+            }
         }
 
-        total
+        Ok(())
     }
 }
 
 #[derive(Debug)]
-enum ParseProgramError {}
+pub(crate) enum ParseProgramError {
+    ReadError(io::Error),
+}
+
+impl Display for ParseProgramError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseProgramError::ReadError(error) => {
+                f.write_fmt(format_args!("could not read program: {}", error))
+            }
+        }
+    }
+}
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum Token {
@@ -74,97 +228,156 @@ enum Token {
     Dont,
 }
 
-fn tokenise(s: &str) -> Result<Vec<Token>, ParseProgramError> {
-    let mut tokens = Vec::new();
-
-    let chars: Vec<char> = s.chars().collect();
+/// Pulls tokens from a reader, only ever buffering as many bytes as the
+/// longest token needs, so tokens split across reads are still found.
+struct Tokeniser<R> {
+    reader: R,
+    lookahead: VecDeque<u8>,
+    offset: usize,
+}
 
-    let mut i = 0;
-    while i < s.len() {
-        let mul_chars = ['m', 'u', 'l'];
-        if i + mul_chars.len() < s.len() && chars[i..i + mul_chars.len()] == mul_chars {
-            tokens.push(Token::Mul);
-            i += 3;
-            continue;
+impl<R: BufRead> Tokeniser<R> {
+    fn new(reader: R) -> Self {
+        Tokeniser {
+            reader,
+            lookahead: VecDeque::new(),
+            offset: 0,
         }
+    }
 
-        if chars[i] == '(' {
-            tokens.push(Token::LBracket);
-            i += 1;
-            continue;
-        }
+    /// Reads until at least `n` bytes are buffered, or the input ends.
+    fn fill_lookahead(&mut self, n: usize) -> io::Result<()> {
+        while self.lookahead.len() < n {
+            let buffer = match self.reader.fill_buf() {
+                Ok(buffer) => buffer,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            if buffer.is_empty() {
+                break;
+            }
 
-        if chars[i] == ')' {
-            tokens.push(Token::RBracket);
-            i += 1;
-            continue;
+            let used = buffer.len().min(n - self.lookahead.len());
+            self.lookahead.extend(&buffer[..used]);
+            self.reader.consume(used);
         }
 
-        if chars[i] == ',' {
-            tokens.push(Token::Comma);
-            i += 1;
-            continue;
-        }
+        Ok(())
+    }
 
-        if let Some(digit) = chars[i].to_digit(10) {
-            let val: u32 = digit;
-            tokens.push(Token::Val(val));
-            i += 1;
-            continue;
-        }
+    fn next_is(&mut self, pattern: &[u8]) -> io::Result<bool> {
+        self.fill_lookahead(pattern.len())?;
+        Ok(self.lookahead.iter().take(pattern.len()).eq(pattern.iter()))
+    }
 
-        let do_chars = ['d', 'o', '(', ')'];
-        if i + do_chars.len() < s.len() && chars[i..i + do_chars.len()] == do_chars {
-            tokens.push(Token::Do);
-            i += do_chars.len();
-            continue;
-        }
+    fn take(&mut self, token: Token, len: usize) -> (Token, Span) {
+        self.lookahead.drain(..len);
+        let span = self.offset..self.offset + len;
+        self.offset += len;
+        (token, span)
+    }
 
-        let dont_chars = ['d', 'o', 'n', '\'', 't', '(', ')'];
-        if i + dont_chars.len() < s.len() && chars[i..i + dont_chars.len()] == dont_chars {
-            tokens.push(Token::Dont);
-            i += dont_chars.len();
-            continue;
-        }
+    fn next_token(&mut self) -> io::Result<Option<(Token, Span)>> {
+        self.fill_lookahead(1)?;
+        let Some(byte) = self.lookahead.front().copied() else {
+            return Ok(None);
+        };
+
+        let token = match byte {
+            b'm' if self.next_is(b"mul")? => self.take(Token::Mul, 3),
+            b'(' => self.take(Token::LBracket, 1),
+            b')' => self.take(Token::RBracket, 1),
+            b',' => self.take(Token::Comma, 1),
+            b'0'..=b'9' => self.take(Token::Val(u32::from(byte - b'0')), 1),
+            b'd' if self.next_is(b"do()")? => self.take(Token::Do, 4),
+            b'd' if self.next_is(b"don't()")? => self.take(Token::Dont, 7),
+            _ => self.take(Token::Invalid, 1),
+        };
+
+        Ok(Some(token))
+    }
+}
+
+impl<R: BufRead> Iterator for Tokeniser<R> {
+    type Item = Result<(Token, Span), ParseProgramError>;
 
-        tokens.push(Token::Invalid);
-        i += 1;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token()
+            .map_err(ParseProgramError::ReadError)
+            .transpose()
     }
+}
+
+/// The most tokens a `mul` can use: `mul ( dddd , dddd )`.
+const MAX_MUL_TOKENS: usize = 12;
 
-    Ok(tokens)
+/// Pulls instructions from a stream of tokens, holding just enough tokens to
+/// recognise one `mul`.
+struct SyntaxAnalysis<I> {
+    tokens: I,
+    window: VecDeque<Token>,
+    spans: VecDeque<Span>,
 }
 
-fn syntax_analsyis(tokens: &[Token]) -> Vec<Instruction> {
-    let mut instructions = Vec::new();
+impl<I: Iterator<Item = Result<(Token, Span), ParseProgramError>>> Iterator for SyntaxAnalysis<I> {
+    type Item = Result<(Instruction, Span), ParseProgramError>;
 
-    let mut i = 0;
-    while i < tokens.len() {
-        let token = tokens[i];
-        if let Token::Mul = token {
-            if let Some((instruction, used_tokens)) = parse_mul(tokens, i) {
-                instructions.push(instruction);
-                i += used_tokens;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while self.window.len() < MAX_MUL_TOKENS {
+                match self.tokens.next() {
+                    Some(Ok((token, span))) => {
+                        self.window.push_back(token);
+                        self.spans.push_back(span);
+                    }
+                    Some(Err(err)) => return Some(Err(err)),
+                    None => break,
+                }
             }
-        }
 
-        if let Token::Do = token {
-            instructions.push(Instruction::Do);
-        }
+            let token = self.window.pop_front()?;
+            let span = self.spans.pop_front()?;
+
+            match token {
+                Token::Mul => {
+                    self.window.push_front(token);
+                    self.spans.push_front(span.clone());
+
+                    if let Some((instruction, used_tokens)) =
+                        parse_mul(self.window.make_contiguous(), 0)
+                    {
+                        let end = self.spans[used_tokens].end;
+                        self.window.drain(..=used_tokens);
+                        self.spans.drain(..=used_tokens);
+                        return Some(Ok((instruction, span.start..end)));
+                    }
 
-        if let Token::Dont = token {
-            instructions.push(Instruction::Dont);
+                    self.window.pop_front();
+                    self.spans.pop_front();
+                }
+                Token::Do => return Some(Ok((Instruction::Do, span))),
+                Token::Dont => return Some(Ok((Instruction::Dont, span))),
+                _ => {}
+            }
         }
-
-        i += 1;
     }
+}
 
-    instructions
+/// Tokenises and analyses `reader` lazily, one instruction at a time.
+fn parse_instructions(
+    reader: impl BufRead,
+) -> impl Iterator<Item = Result<(Instruction, Span), ParseProgramError>> {
+    SyntaxAnalysis {
+        tokens: Tokeniser::new(reader),
+        window: VecDeque::with_capacity(MAX_MUL_TOKENS),
+        spans: VecDeque::with_capacity(MAX_MUL_TOKENS),
+    }
 }
 
 fn parse_mul(tokens: &[Token], start_index: usize) -> Option<(Instruction, usize)> {
     let mut i = 0;
 
-    if tokens[start_index + i] != Token::Mul {
+    if tokens.get(start_index + i) != Some(&Token::Mul) {
         return None;
     }
 
@@ -172,7 +385,7 @@ fn parse_mul(tokens: &[Token], start_index: usize) -> Option<(Instruction, usize
 
     i += 1;
 
-    if tokens[start_index + i] != Token::LBracket {
+    if tokens.get(start_index + i) != Some(&Token::LBracket) {
         return None;
     }
 
@@ -185,7 +398,7 @@ fn parse_mul(tokens: &[Token], start_index: usize) -> Option<(Instruction, usize
     log::trace!("got left val {}", left_val);
     i += token_size;
 
-    if tokens[start_index + i] != Token::Comma {
+    if tokens.get(start_index + i) != Some(&Token::Comma) {
         return None;
     }
 
@@ -199,7 +412,7 @@ fn parse_mul(tokens: &[Token], start_index: usize) -> Option<(Instruction, usize
 
     i += token_size;
 
-    if tokens[start_index + i] != Token::RBracket {
+    if tokens.get(start_index + i) != Some(&Token::RBracket) {
         return None;
     }
 
@@ -220,7 +433,7 @@ fn parse_val(tokens: &[Token], start_index: usize) -> Option<(u32, usize)> {
             for (k, v) in vals.iter().enumerate() {
                 const TEN: u32 = 10;
                 let power = TEN.pow((vals.len() - k - 1) as u32);
-                total += v * power as u32;
+                total += v * power;
             }
 
             return Some((total, i));
@@ -246,44 +459,193 @@ impl FromStr for Program {
     type Err = ParseProgramError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let tokens = tokenise(s)?;
-        log::debug!("tokens: {:?}", tokens);
+        Program::from_reader(s.as_bytes())
+    }
+}
 
-        let instructions = syntax_analsyis(&tokens);
+/// Opens the corrupted memory to scan, with `-` meaning stdin, as the
+/// interpreters stream it rather than reading it all up front.
+fn open_input() -> Box<dyn BufRead> {
+    let args: Vec<String> = env::args().collect();
+    let path = args.get(1).unwrap();
 
-        Ok(Program { instructions })
+    if path == "-" {
+        return Box::new(io::stdin().lock());
     }
+
+    let file = File::open(path).expect("Should have been able to read the file");
+    Box::new(BufReader::new(file))
 }
 
-fn load_input() -> String {
-    let args: Vec<String> = env::args().collect();
-    fs::read_to_string(args.get(1).unwrap()).expect("Should have been able to read the file")
+/// The integer type the interpreters total into, chosen with `--result`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResultType {
+    U32,
+    U64,
+    U128,
 }
 
-fn main() -> Result<(), ParseProgramError> {
-    env_logger::init();
+#[derive(Debug)]
+enum ParseOptionsError {
+    UnknownFlag(String),
+    MissingValue(String),
+    UnknownResultType(String),
+}
+
+impl Display for ParseOptionsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseOptionsError::UnknownFlag(flag) => {
+                f.write_fmt(format_args!("unknown flag: {}", flag))
+            }
+            ParseOptionsError::MissingValue(flag) => {
+                f.write_fmt(format_args!("missing value for {}", flag))
+            }
+            ParseOptionsError::UnknownResultType(result_type) => {
+                f.write_fmt(format_args!("unknown result type: {}", result_type))
+            }
+        }
+    }
+}
 
-    let input = load_input();
-    let program: Program = input.parse()?;
+/// Whether the corrupted program is disassembled or run, and the integer
+/// type a run totals into.
+#[derive(Debug, PartialEq, Eq)]
+struct Options {
+    result_type: ResultType,
+    disassemble: bool,
+}
 
-    log::debug!("{:?}", program);
+impl Options {
+    /// Builds options from `--result u32|u64|u128`, which defaults to `u64`,
+    /// and `--disassemble`, which prints the program instead of running it.
+    fn from_args(args: &[String]) -> Result<Options, ParseOptionsError> {
+        let mut options = Options {
+            result_type: ResultType::U64,
+            disassemble: false,
+        };
+
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            if flag == "--disassemble" {
+                options.disassemble = true;
+                continue;
+            }
 
-    let interpreter1 = Interpreter1 {};
+            if flag != "--result" {
+                return Err(ParseOptionsError::UnknownFlag(flag.clone()));
+            }
 
-    let result = interpreter1.run(&program);
-    println!("{}", result);
+            let result_type = args
+                .next()
+                .ok_or_else(|| ParseOptionsError::MissingValue(flag.clone()))?;
+            options.result_type = match result_type.as_str() {
+                "u32" => ResultType::U32,
+                "u64" => ResultType::U64,
+                "u128" => ResultType::U128,
+                _ => return Err(ParseOptionsError::UnknownResultType(result_type.clone())),
+            };
+        }
 
-    let mut interpreter2 = Interpreter2 {
-        instructions_enabled: true,
-    };
-    let result2 = interpreter2.run(&program);
+        Ok(options)
+    }
+}
+
+#[derive(Debug)]
+enum RunError {
+    ParseError(ParseProgramError),
+    OverflowError(OverflowError),
+}
+
+impl From<ParseProgramError> for RunError {
+    fn from(error: ParseProgramError) -> Self {
+        RunError::ParseError(error)
+    }
+}
+
+impl From<OverflowError> for RunError {
+    fn from(error: OverflowError) -> Self {
+        RunError::OverflowError(error)
+    }
+}
+
+impl Display for RunError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunError::ParseError(error) => error.fmt(f),
+            RunError::OverflowError(error) => error.fmt(f),
+        }
+    }
+}
+
+/// Runs both interpreters side by side in a single pass over `instructions`.
+fn run_interpreters<T: Accumulator>(
+    instructions: impl Iterator<Item = Result<(Instruction, Span), ParseProgramError>>,
+) -> Result<(T, T), RunError> {
+    let mut interpreter1 = Interpreter1::new();
+    let mut interpreter2 = Interpreter2::new();
+
+    for (i, instruction) in instructions.enumerate() {
+        let (instruction, span) = instruction?;
+        log::debug!("{}: {:?} at {:?}", i, instruction, span);
+
+        interpreter1.execute(i, &instruction, &span)?;
+        interpreter2.execute(i, &instruction, &span)?;
+    }
+
+    Ok((interpreter1.total, interpreter2.total))
+}
+
+fn print_results<T: Accumulator>(
+    instructions: impl Iterator<Item = Result<(Instruction, Span), ParseProgramError>>,
+) -> Result<(), RunError> {
+    let (result, result2) = run_interpreters::<T>(instructions)?;
+    println!("{}", result);
     println!("{}", result2);
     Ok(())
 }
 
+fn main() {
+    env_logger::init();
+
+    let args: Vec<String> = env::args().collect();
+    let options = Options::from_args(args.get(2..).unwrap_or_default()).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        eprintln!("usage: three <input|-> [--result u32|u64|u128] [--disassemble]");
+        process::exit(1);
+    });
+
+    if options.disassemble {
+        let program = Program::from_reader(open_input()).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(1);
+        });
+        println!("{}", program);
+        return;
+    }
+
+    let instructions = parse_instructions(open_input());
+
+    let result = match options.result_type {
+        ResultType::U32 => print_results::<u32>(instructions),
+        ResultType::U64 => print_results::<u64>(instructions),
+        ResultType::U128 => print_results::<u128>(instructions),
+    };
+
+    result.unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{parse_mul, parse_val, Instruction, Token};
+    use std::io::{BufReader, Read};
+
+    use crate::{
+        parse_instructions, parse_mul, parse_val, run_interpreters, Instruction, OverflowError,
+        Program, RunError, Token,
+    };
 
     #[test]
     fn test_parse_val() {
@@ -321,4 +683,95 @@ mod tests {
         let val = parse_mul(&tokens, 0);
         assert_eq!(Some((Instruction::Mul(2, 1), 5)), val);
     }
+
+    #[test]
+    fn test_overflow() {
+        // 43 * 9999 * 9999 is just past u32::MAX.
+        let input = "mul(9999,9999)".repeat(43);
+
+        let result = run_interpreters::<u32>(parse_instructions(input.as_bytes()));
+        assert!(matches!(
+            result,
+            Err(RunError::OverflowError(OverflowError {
+                result_type: "u32",
+                instruction_index: 42,
+                span: std::ops::Range {
+                    start: 588,
+                    end: 602
+                },
+            }))
+        ));
+
+        let result = run_interpreters::<u64>(parse_instructions(input.as_bytes()));
+        assert!(matches!(result, Ok((4_299_140_043, 4_299_140_043))));
+    }
+
+    /// Hands out one byte per read, so every multi-byte token is split.
+    struct OneByteAtATime<'a>(&'a [u8]);
+
+    impl Read for OneByteAtATime<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = buf.len().min(1);
+            self.0.read(&mut buf[..len])
+        }
+    }
+
+    #[test]
+    fn test_streaming() {
+        let input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        let program: Program = input.parse().unwrap();
+
+        let reader = BufReader::with_capacity(1, OneByteAtATime(input.as_bytes()));
+        let streamed: Vec<(Instruction, std::ops::Range<usize>)> =
+            parse_instructions(reader).map(Result::unwrap).collect();
+        assert_eq!(program.instructions, streamed);
+
+        assert_eq!(
+            vec![
+                (Instruction::Mul(2, 4), 1..9),
+                (Instruction::Dont, 20..27),
+                (Instruction::Mul(5, 5), 28..36),
+                (Instruction::Mul(11, 8), 48..57),
+                (Instruction::Do, 59..63),
+                (Instruction::Mul(8, 5), 64..72),
+            ],
+            streamed
+        );
+
+        let result = run_interpreters::<u64>(program.instructions.into_iter().map(Ok));
+        assert!(matches!(result, Ok((161, 48))));
+
+        // A `mul` cut short by the end of the input.
+        let program: Program = "mul(2,4)mul".parse().unwrap();
+        assert_eq!(vec![(Instruction::Mul(2, 4), 0..8)], program.instructions);
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let program: Program = "mul(2,4)don't()mul(5,5)do()mul(8,5)".parse().unwrap();
+
+        let expected = [
+            "   index  offsets           instruction       status    total",
+            "       0  0..8              mul(2,4)          enabled   8",
+            "       1  8..15             don't()                     8",
+            "       2  15..23            mul(5,5)          disabled  8",
+            "       3  23..27            do()                        8",
+            "       4  27..35            mul(8,5)          enabled   48",
+        ];
+        assert_eq!(expected.join("\n"), program.to_string());
+    }
+
+    /// Inputs that once made the `program_from_str` fuzz target panic.
+    #[test]
+    fn test_fuzz_regressions() {
+        let dir = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fuzz/regressions/program_from_str"
+        );
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let input = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+            let program: Program = input.parse().unwrap();
+            let _ = program.to_string();
+        }
+    }
 }
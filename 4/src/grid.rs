@@ -1,4 +1,6 @@
+use std::env;
 use std::fmt::{Display, Write};
+use std::io::IsTerminal;
 use std::str::FromStr;
 
 pub struct Grid<T> {
@@ -7,10 +9,55 @@ pub struct Grid<T> {
     length: usize,
 }
 
+/// Where in the input a parse error occurred, with the offending line kept so
+/// it can be shown with a caret under the bad column. Lines and columns are
+/// one-based and columns count characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub line: usize,
+    pub column: usize,
+    source_line: String,
+}
+
+impl SourceLocation {
+    fn new(line_index: usize, column_index: usize, source_line: &str) -> SourceLocation {
+        SourceLocation {
+            line: line_index + 1,
+            column: column_index + 1,
+            source_line: source_line.to_string(),
+        }
+    }
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let gutter = " ".repeat(self.line.to_string().len());
+
+        f.write_fmt(format_args!(
+            "{} --> line {}, column {}\n",
+            gutter, self.line, self.column
+        ))?;
+        f.write_fmt(format_args!("{} |\n", gutter))?;
+        f.write_fmt(format_args!("{} | {}\n", self.line, self.source_line))?;
+        f.write_fmt(format_args!(
+            "{} | {}^",
+            gutter,
+            " ".repeat(self.column - 1)
+        ))
+    }
+}
+
 #[derive(Debug)]
 pub enum ParseGridError<ValError> {
-    DifferingRowSizes { expected: usize, got: usize },
-    ParseValError(ValError),
+    DifferingRowSizes {
+        expected: usize,
+        got: usize,
+        location: SourceLocation,
+    },
+    ParseValError {
+        error: ValError,
+        location: SourceLocation,
+    },
 }
 
 impl<T> Display for ParseGridError<T>
@@ -19,14 +66,18 @@ where
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ParseGridError::DifferingRowSizes { expected, got } => {
+            ParseGridError::DifferingRowSizes {
+                expected,
+                got,
+                location,
+            } => {
                 f.write_fmt(format_args!(
-                    "got differing row sizes, expected {}, got {}",
-                    expected, got,
+                    "got differing row sizes, expected {}, got {}\n{}",
+                    expected, got, location,
                 ))?;
             }
-            ParseGridError::ParseValError(v) => {
-                f.write_fmt(format_args!("ParseValError: {}", v))?;
+            ParseGridError::ParseValError { error, location } => {
+                f.write_fmt(format_args!("ParseValError: {}\n{}", error, location))?;
             }
         }
         Ok(())
@@ -43,34 +94,42 @@ where
         let mut values: Vec<T> = Vec::new();
         let mut width = None;
         let mut length = 0;
-        for line in s.split('\n').filter(|line| !line.is_empty()) {
+        for (line_index, line) in s
+            .split('\n')
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+        {
+            let num_chars = line.chars().count();
             match width {
                 Some(existing_width) => {
-                    if line.len() != existing_width {
+                    if num_chars != existing_width {
+                        let offset = existing_width.min(num_chars);
                         return Err(ParseGridError::DifferingRowSizes {
                             expected: existing_width,
-                            got: line.len(),
+                            got: num_chars,
+                            location: SourceLocation::new(line_index, offset, line),
                         });
                     }
                 }
                 None => {
-                    width = Some(line.len());
+                    width = Some(num_chars);
                 }
             }
 
-            let mut row = line
-                .chars()
-                .map(|c| T::try_from(c))
-                .collect::<Result<Vec<T>, T::Error>>()
-                .map_err(ParseGridError::ParseValError)?;
+            for (column, c) in line.chars().enumerate() {
+                let value = T::try_from(c).map_err(|error| ParseGridError::ParseValError {
+                    error,
+                    location: SourceLocation::new(line_index, column, line),
+                })?;
+                values.push(value);
+            }
 
-            values.append(&mut row);
             length += 1;
         }
 
         Ok(Grid {
             values,
-            width: width.unwrap(),
+            width: width.unwrap_or(0),
             length,
         })
     }
@@ -97,6 +156,7 @@ impl<T> Grid<T> {
         self.length
     }
 
+    #[allow(dead_code)]
     pub fn map_elements<U>(&self, map_function: impl Fn(((usize, usize), &T)) -> U) -> Grid<U> {
         let values = (0..self.length)
             .flat_map(|y| (0..self.width).map(move |x| (x, y)))
@@ -136,3 +196,273 @@ where
         Ok(())
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum Colour {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl Colour {
+    fn ansi_offset(self) -> u8 {
+        match self {
+            Colour::Black => 0,
+            Colour::Red => 1,
+            Colour::Green => 2,
+            Colour::Yellow => 3,
+            Colour::Blue => 4,
+            Colour::Magenta => 5,
+            Colour::Cyan => 6,
+            Colour::White => 7,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style {
+    pub foreground: Option<Colour>,
+    pub background: Option<Colour>,
+}
+
+impl Style {
+    pub fn foreground(colour: Colour) -> Style {
+        Style {
+            foreground: Some(colour),
+            background: None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn background(colour: Colour) -> Style {
+        Style {
+            foreground: None,
+            background: Some(colour),
+        }
+    }
+
+    /// Layers `other` on top of this style, keeping any colour `other`
+    /// leaves unset.
+    fn layer(self, other: Style) -> Style {
+        Style {
+            foreground: other.foreground.or(self.foreground),
+            background: other.background.or(self.background),
+        }
+    }
+
+    fn write_start(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let codes: Vec<String> = [
+            self.foreground.map(|c| 30 + c.ansi_offset()),
+            self.background.map(|c| 40 + c.ansi_offset()),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|code| code.to_string())
+        .collect();
+
+        if codes.is_empty() {
+            return Ok(());
+        }
+
+        f.write_fmt(format_args!("\x1b[{}m", codes.join(";")))
+    }
+
+    fn write_end(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if *self == Style::default() {
+            return Ok(());
+        }
+
+        f.write_str("\x1b[0m")
+    }
+}
+
+type StyleFunction<'a, T> = Box<dyn Fn((i32, i32), &T) -> Style + 'a>;
+
+/// Draws a grid with per-cell colours and highlighted overlays. Built with
+/// `Grid::render` and written out through `Display`.
+///
+/// Colours are only used when stderr is a terminal and `NO_COLOR` is unset.
+/// Without colours, cells outside every overlay are drawn with the fallback
+/// mask character instead, so highlights stay visible in plain logs.
+pub struct GridRender<'a, T> {
+    grid: &'a Grid<T>,
+    style: StyleFunction<'a, T>,
+    overlays: Vec<(&'a GridSet, Style)>,
+    colour: bool,
+    fallback_mask: Option<char>,
+}
+
+impl<T> Grid<T> {
+    pub fn render(&self) -> GridRender<'_, T> {
+        GridRender {
+            grid: self,
+            style: Box::new(|_, _| Style::default()),
+            overlays: Vec::new(),
+            colour: std::io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none(),
+            fallback_mask: Some('.'),
+        }
+    }
+}
+
+impl<'a, T> GridRender<'a, T> {
+    #[allow(dead_code)]
+    pub fn with_style(mut self, style: impl Fn((i32, i32), &T) -> Style + 'a) -> Self {
+        self.style = Box::new(style);
+        self
+    }
+
+    /// Highlights `cells` with `style`. Later overlays are drawn on top of
+    /// earlier ones.
+    pub fn with_overlay(mut self, cells: &'a GridSet, style: Style) -> Self {
+        self.overlays.push((cells, style));
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_colour(mut self, colour: bool) -> Self {
+        self.colour = colour;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_fallback_mask(mut self, mask: Option<char>) -> Self {
+        self.fallback_mask = mask;
+        self
+    }
+}
+
+impl<T> GridRender<'_, T>
+where
+    T: Display,
+{
+    fn write_cell(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        x: i32,
+        y: i32,
+        value: &T,
+    ) -> std::fmt::Result {
+        let mut overlays = self
+            .overlays
+            .iter()
+            .filter(|(cells, _)| cells.contains(x, y))
+            .peekable();
+
+        if self.colour {
+            let style = overlays.fold((self.style)((x, y), value), |style, (_, overlay)| {
+                style.layer(*overlay)
+            });
+
+            style.write_start(f)?;
+            f.write_fmt(format_args!("{}", value))?;
+            return style.write_end(f);
+        }
+
+        match self.fallback_mask {
+            Some(mask) if !self.overlays.is_empty() && overlays.peek().is_none() => {
+                f.write_char(mask)
+            }
+            _ => f.write_fmt(format_args!("{}", value)),
+        }
+    }
+}
+
+impl<T> Display for GridRender<'_, T>
+where
+    T: Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self.grid.get_width() as i32;
+        let height = self.grid.get_height() as i32;
+
+        f.write_char('\n')?;
+
+        for y in 0..height {
+            for x in 0..width {
+                let value = self.grid.get(x, y).unwrap();
+                self.write_cell(f, x, y, value)?;
+
+                if x == width - 1 {
+                    f.write_char('\n')?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A set of grid cells, stored as a bitset so membership checks don't hash
+/// or allocate.
+#[derive(Clone)]
+pub struct GridSet {
+    bits: Vec<u64>,
+    width: usize,
+    length: usize,
+}
+
+impl GridSet {
+    pub fn for_grid<T>(grid: &Grid<T>) -> GridSet {
+        let num_bits = grid.get_width() * grid.get_height();
+
+        GridSet {
+            bits: vec![0; num_bits.div_ceil(u64::BITS as usize)],
+            width: grid.get_width(),
+            length: grid.get_height(),
+        }
+    }
+
+    /// Cells outside the grid are ignored.
+    pub fn insert(&mut self, x: i32, y: i32) {
+        if let Some((word, mask)) = self.locate(x, y) {
+            self.bits[word] |= mask;
+        }
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        match self.locate(x, y) {
+            Some((word, mask)) => self.bits[word] & mask != 0,
+            None => false,
+        }
+    }
+
+    fn locate(&self, x: i32, y: i32) -> Option<(usize, u64)> {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.length as i32 {
+            return None;
+        }
+
+        let index = y as usize * self.width + x as usize;
+        let word_bits = u64::BITS as usize;
+        Some((index / word_bits, 1 << (index % word_bits)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Grid, GridSet};
+
+    #[test]
+    fn test_grid_set() {
+        let grid: Grid<char> = ["........"; 9].join("\n").parse().unwrap();
+        let mut set = GridSet::for_grid(&grid);
+
+        // (7, 7) is bit 63 and (0, 8) is bit 64, so they sit either side of a
+        // word boundary.
+        set.insert(7, 7);
+        assert!(set.contains(7, 7));
+        assert!(!set.contains(0, 8));
+        assert!(!set.contains(6, 7));
+
+        for (x, y) in [(-1, 0), (0, -1), (8, 0), (0, 9)] {
+            set.insert(x, y);
+            assert!(!set.contains(x, y));
+        }
+        assert!(!set.contains(0, 0));
+    }
+}
@@ -1,11 +1,11 @@
 mod grid;
 
-use grid::Grid;
+use grid::{Colour, Grid, GridSet, Style};
 use itertools::Itertools;
-use std::collections::HashSet;
 use std::env;
 use std::fmt::{Display, Write};
 use std::fs;
+use std::process;
 
 fn load_input() -> String {
     let args: Vec<String> = env::args().collect();
@@ -16,7 +16,10 @@ fn main() {
     env_logger::init();
 
     let input = load_input();
-    let grid: Grid<Letter> = input.parse().unwrap();
+    let grid: Grid<Letter> = input.parse().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
 
     log::debug!("{}", grid);
 
@@ -24,14 +27,22 @@ fn main() {
     assert!(word_locations.iter().all(|word| word.len() == 4));
     let num_occurances = word_locations.len();
 
-    let active_grid = create_active_grid(&grid, word_locations);
-    log::debug!("{}", active_grid);
+    let active_characters = find_active_characters(&grid, &word_locations);
+    log::debug!(
+        "{}",
+        grid.render()
+            .with_overlay(&active_characters, Style::foreground(Colour::Red))
+    );
     println!("{}", num_occurances);
 
     let word_locations = search_x_mas(&grid);
     let num_occurances = word_locations.len();
-    let active_grid = create_active_grid(&grid, word_locations);
-    log::debug!("{}", active_grid);
+    let active_characters = find_active_characters(&grid, &word_locations);
+    log::debug!(
+        "{}",
+        grid.render()
+            .with_overlay(&active_characters, Style::foreground(Colour::Red))
+    );
     println!("{}", num_occurances);
 }
 
@@ -203,19 +214,13 @@ fn get_word_rotations(target_word: &[Letter]) -> Vec<Vec<(i32, i32)>> {
         .collect()
 }
 
-fn create_active_grid(grid: &Grid<Letter>, word_locations: Vec<WordLocation>) -> Grid<String> {
-    let mut active_characters = HashSet::new();
+fn find_active_characters(grid: &Grid<Letter>, word_locations: &[WordLocation]) -> GridSet {
+    let mut active_characters = GridSet::for_grid(grid);
     word_locations.iter().for_each(|word| {
-        word.iter().for_each(|position| {
-            active_characters.insert(position);
+        word.iter().for_each(|(x, y)| {
+            active_characters.insert(*x as i32, *y as i32);
         })
     });
-    
-
-    grid.map_elements(
-            |(position, v)| match &active_characters.contains(&position) {
-                true => format!("{}", v),
-                false => ".".to_string(),
-            },
-        )
+
+    active_characters
 }
@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Only parsing is fuzzed, so most of the grid API goes unused here.
+#[allow(dead_code)]
+#[path = "../../src/grid.rs"]
+mod grid;
+
+use grid::Grid;
+
+// Parsing must return `Ok` or `Err` for any input, never panic. `u8` cells
+// also exercise the `ParseValError` path, as most characters don't fit.
+fuzz_target!(|s: &str| {
+    let _ = s.parse::<Grid<char>>();
+    let _ = s.parse::<Grid<u8>>();
+});
@@ -1,4 +1,7 @@
+use std::env;
 use std::fmt::{Display, Write};
+use std::io::IsTerminal;
+use std::marker::PhantomData;
 use std::str::FromStr;
 
 #[derive(Clone)]
@@ -8,10 +11,60 @@ pub struct Grid<T> {
     length: usize,
 }
 
+/// Where in the input a parse error occurred, with the offending line kept so
+/// it can be shown with a caret under the bad column. Lines and columns are
+/// one-based and columns count characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub line: usize,
+    pub column: usize,
+    source_line: String,
+}
+
+impl SourceLocation {
+    fn new(line_index: usize, column_index: usize, source_line: &str) -> SourceLocation {
+        SourceLocation {
+            line: line_index + 1,
+            column: column_index + 1,
+            source_line: source_line.to_string(),
+        }
+    }
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let gutter = " ".repeat(self.line.to_string().len());
+
+        f.write_fmt(format_args!(
+            "{} --> line {}, column {}\n",
+            gutter, self.line, self.column
+        ))?;
+        f.write_fmt(format_args!("{} |\n", gutter))?;
+        f.write_fmt(format_args!("{} | {}\n", self.line, self.source_line))?;
+        f.write_fmt(format_args!(
+            "{} | {}^",
+            gutter,
+            " ".repeat(self.column - 1)
+        ))
+    }
+}
+
 #[derive(Debug)]
 pub enum ParseGridError<ValError> {
-    DifferingRowSizes { expected: usize, got: usize },
-    ParseValError(ValError),
+    DifferingRowSizes {
+        expected: usize,
+        got: usize,
+        location: SourceLocation,
+    },
+    IncompleteCell {
+        location: SourceLocation,
+    },
+    #[allow(dead_code)]
+    ZeroCellWidth,
+    ParseValError {
+        error: ValError,
+        location: SourceLocation,
+    },
 }
 
 impl<T> Display for ParseGridError<T>
@@ -20,20 +73,83 @@ where
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ParseGridError::DifferingRowSizes { expected, got } => {
+            ParseGridError::DifferingRowSizes {
+                expected,
+                got,
+                location,
+            } => {
                 f.write_fmt(format_args!(
-                    "got differing row sizes, expected {}, got {}",
-                    expected, got,
+                    "got differing row sizes, expected {}, got {}\n{}",
+                    expected, got, location,
                 ))?;
             }
-            ParseGridError::ParseValError(v) => {
-                f.write_fmt(format_args!("ParseValError: {}", v))?;
+            ParseGridError::IncompleteCell { location } => {
+                f.write_fmt(format_args!("got incomplete cell\n{}", location))?;
+            }
+            ParseGridError::ZeroCellWidth => {
+                f.write_str("cell width must be non-zero")?;
+            }
+            ParseGridError::ParseValError { error, location } => {
+                f.write_fmt(format_args!("ParseValError: {}\n{}", error, location))?;
             }
         }
         Ok(())
     }
 }
 
+/// How a line of input is split into the text of each cell.
+#[allow(dead_code)]
+enum CellLayout {
+    Chars,
+    Delimited(char),
+    FixedWidth(usize),
+}
+
+impl CellLayout {
+    /// Returns each cell's text with the character offset it starts at, or
+    /// the offset of a cell that is cut short by the end of the line.
+    fn split<'a>(&self, line: &'a str) -> Result<Vec<(usize, &'a str)>, usize> {
+        match self {
+            CellLayout::Chars => Ok(line
+                .char_indices()
+                .enumerate()
+                .map(|(offset, (i, c))| (offset, &line[i..i + c.len_utf8()]))
+                .collect()),
+            CellLayout::Delimited(delimiter) => {
+                let mut offset = 0;
+                Ok(line
+                    .split(*delimiter)
+                    .map(|cell| {
+                        let start = offset;
+                        offset += cell.chars().count() + 1;
+                        (start, cell)
+                    })
+                    .collect())
+            }
+            CellLayout::FixedWidth(cell_width) => {
+                let boundaries: Vec<usize> = line
+                    .char_indices()
+                    .map(|(i, _)| i)
+                    .chain([line.len()])
+                    .collect();
+                let num_chars = boundaries.len() - 1;
+
+                (0..num_chars)
+                    .step_by(*cell_width)
+                    .map(|start| {
+                        let end = start + cell_width;
+                        if end > num_chars {
+                            return Err(start);
+                        }
+
+                        Ok((start, &line[boundaries[start]..boundaries[end]]))
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
 impl<T> FromStr for Grid<T>
 where
     T: TryFrom<char>,
@@ -41,43 +157,100 @@ where
     type Err = ParseGridError<T::Error>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Grid::from_cells(s, CellLayout::Chars, |cell| {
+            T::try_from(cell.chars().next().unwrap())
+        })
+    }
+}
+
+#[allow(dead_code)]
+impl<T> Grid<T>
+where
+    T: FromStr,
+{
+    /// Parses a grid whose cells are separated by `delimiter`, e.g. space
+    /// separated numbers. Each cell is trimmed before being parsed.
+    pub fn from_str_delimited(s: &str, delimiter: char) -> Result<Self, ParseGridError<T::Err>> {
+        Grid::from_cells(s, CellLayout::Delimited(delimiter), |cell| {
+            cell.trim().parse()
+        })
+    }
+
+    /// Parses a grid whose cells are each exactly `cell_width` characters
+    /// wide. Each cell is trimmed before being parsed, so values may be
+    /// padded with spaces. A `cell_width` of zero is an error.
+    pub fn from_str_fixed_width(
+        s: &str,
+        cell_width: usize,
+    ) -> Result<Self, ParseGridError<T::Err>> {
+        if cell_width == 0 {
+            return Err(ParseGridError::ZeroCellWidth);
+        }
+
+        Grid::from_cells(s, CellLayout::FixedWidth(cell_width), |cell| {
+            cell.trim().parse()
+        })
+    }
+}
+
+impl<T> Grid<T> {
+    fn from_cells<E>(
+        s: &str,
+        layout: CellLayout,
+        parse_cell: impl Fn(&str) -> Result<T, E>,
+    ) -> Result<Self, ParseGridError<E>> {
         let mut values: Vec<T> = Vec::new();
         let mut width = None;
         let mut length = 0;
-        for line in s.split('\n').filter(|line| !line.is_empty()) {
+        for (line_index, line) in s
+            .split('\n')
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+        {
+            let cells = layout
+                .split(line)
+                .map_err(|offset| ParseGridError::IncompleteCell {
+                    location: SourceLocation::new(line_index, offset, line),
+                })?;
+
             match width {
                 Some(existing_width) => {
-                    if line.len() != existing_width {
+                    if cells.len() != existing_width {
+                        let offset = match cells.get(existing_width) {
+                            Some((offset, _)) => *offset,
+                            None => line.chars().count(),
+                        };
+
                         return Err(ParseGridError::DifferingRowSizes {
                             expected: existing_width,
-                            got: line.len(),
+                            got: cells.len(),
+                            location: SourceLocation::new(line_index, offset, line),
                         });
                     }
                 }
                 None => {
-                    width = Some(line.len());
+                    width = Some(cells.len());
                 }
             }
 
-            let mut row = line
-                .chars()
-                .map(|c| T::try_from(c))
-                .collect::<Result<Vec<T>, T::Error>>()
-                .map_err(ParseGridError::ParseValError)?;
+            for (offset, cell) in cells {
+                let value = parse_cell(cell).map_err(|error| ParseGridError::ParseValError {
+                    error,
+                    location: SourceLocation::new(line_index, offset, line),
+                })?;
+                values.push(value);
+            }
 
-            values.append(&mut row);
             length += 1;
         }
 
         Ok(Grid {
             values,
-            width: width.unwrap(),
+            width: width.unwrap_or(0),
             length,
         })
     }
-}
 
-impl<T> Grid<T> {
     pub fn get(&self, x: i32, y: i32) -> Option<&T> {
         let index = self.calculate_index(x, y)?;
         self.values.get(index)
@@ -103,13 +276,14 @@ impl<T> Grid<T> {
         self.length
     }
 
-    pub fn iter(&self) -> GridIterator<T> {
-        return GridIterator {
+    pub fn iter(&self) -> GridIterator<'_, T> {
+        GridIterator {
             grid: self,
             cursor: (0, 0),
-        };
+        }
     }
 
+    #[allow(dead_code)]
     pub fn map_elements<U>(&self, map_function: impl Fn(((usize, usize), &T)) -> U) -> Grid<U> {
         let values = (0..self.length)
             .flat_map(|y| (0..self.width).map(move |x| (x, y)))
@@ -132,6 +306,105 @@ impl<T> Grid<T> {
     }
 }
 
+#[cfg(feature = "serde")]
+mod serialisation {
+    use super::Grid;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize)]
+    struct GridRef<'a, T> {
+        width: usize,
+        height: usize,
+        values: &'a [T],
+    }
+
+    #[derive(Deserialize)]
+    struct GridData<T> {
+        width: usize,
+        height: usize,
+        values: Vec<T>,
+    }
+
+    impl<T> Serialize for Grid<T>
+    where
+        T: Serialize,
+    {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            GridRef {
+                width: self.width,
+                height: self.length,
+                values: &self.values,
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de, T> Deserialize<'de> for Grid<T>
+    where
+        T: Deserialize<'de>,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let data = GridData::deserialize(deserializer)?;
+
+            let expected = data.width.checked_mul(data.height).ok_or_else(|| {
+                D::Error::custom(format!(
+                    "a {}x{} grid is too large",
+                    data.width, data.height
+                ))
+            })?;
+            if data.values.len() != expected {
+                return Err(D::Error::custom(format!(
+                    "expected {} values for a {}x{} grid, got {}",
+                    expected,
+                    data.width,
+                    data.height,
+                    data.values.len(),
+                )));
+            }
+
+            Ok(Grid {
+                values: data.values,
+                width: data.width,
+                length: data.height,
+            })
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use crate::grid::Grid;
+
+        #[test]
+        fn test_round_trip() {
+            let grid: Grid<u8> = Grid {
+                values: vec![1, 2, 3, 4, 5, 6],
+                width: 3,
+                length: 2,
+            };
+
+            let json = serde_json::to_string(&grid).unwrap();
+            assert_eq!(r#"{"width":3,"height":2,"values":[1,2,3,4,5,6]}"#, json);
+
+            let loaded: Grid<u8> = serde_json::from_str(&json).unwrap();
+            assert_eq!(grid.values, loaded.values);
+            assert_eq!(3, loaded.get_width());
+            assert_eq!(2, loaded.get_height());
+        }
+
+        #[test]
+        fn test_rejects_mismatched_dimensions() {
+            let loaded = serde_json::from_str::<Grid<u8>>(r#"{"width":3,"height":2,"values":[1]}"#);
+            assert!(loaded.is_err());
+
+            let loaded = serde_json::from_str::<Grid<u8>>(
+                r#"{"width":4294967296,"height":4294967296,"values":[]}"#,
+            );
+            assert!(loaded.is_err());
+        }
+    }
+}
+
 impl<T> Display for Grid<T>
 where
     T: Display,
@@ -182,6 +455,464 @@ impl<'a, T> Iterator for GridIterator<'a, T> {
             self.cursor = (x + 1, y);
         }
 
-        return Some(((x, y), item));
+        Some(((x, y), item))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum Colour {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl Colour {
+    fn ansi_offset(self) -> u8 {
+        match self {
+            Colour::Black => 0,
+            Colour::Red => 1,
+            Colour::Green => 2,
+            Colour::Yellow => 3,
+            Colour::Blue => 4,
+            Colour::Magenta => 5,
+            Colour::Cyan => 6,
+            Colour::White => 7,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style {
+    pub foreground: Option<Colour>,
+    pub background: Option<Colour>,
+}
+
+impl Style {
+    pub fn foreground(colour: Colour) -> Style {
+        Style {
+            foreground: Some(colour),
+            background: None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn background(colour: Colour) -> Style {
+        Style {
+            foreground: None,
+            background: Some(colour),
+        }
+    }
+
+    /// Layers `other` on top of this style, keeping any colour `other`
+    /// leaves unset.
+    fn layer(self, other: Style) -> Style {
+        Style {
+            foreground: other.foreground.or(self.foreground),
+            background: other.background.or(self.background),
+        }
+    }
+
+    fn write_start(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let codes: Vec<String> = [
+            self.foreground.map(|c| 30 + c.ansi_offset()),
+            self.background.map(|c| 40 + c.ansi_offset()),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|code| code.to_string())
+        .collect();
+
+        if codes.is_empty() {
+            return Ok(());
+        }
+
+        f.write_fmt(format_args!("\x1b[{}m", codes.join(";")))
+    }
+
+    fn write_end(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if *self == Style::default() {
+            return Ok(());
+        }
+
+        f.write_str("\x1b[0m")
+    }
+}
+
+type StyleFunction<'a, T> = Box<dyn Fn((i32, i32), &T) -> Style + 'a>;
+
+/// Draws a grid with per-cell colours and highlighted overlays. Built with
+/// `Grid::render` and written out through `Display`.
+///
+/// Colours are only used when stderr is a terminal and `NO_COLOR` is unset.
+/// Without colours, cells outside every overlay are drawn with the fallback
+/// mask character instead, so highlights stay visible in plain logs.
+pub struct GridRender<'a, T> {
+    grid: &'a Grid<T>,
+    style: StyleFunction<'a, T>,
+    overlays: Vec<(&'a GridSet, Style)>,
+    colour: bool,
+    fallback_mask: Option<char>,
+}
+
+impl<T> Grid<T> {
+    pub fn render(&self) -> GridRender<'_, T> {
+        GridRender {
+            grid: self,
+            style: Box::new(|_, _| Style::default()),
+            overlays: Vec::new(),
+            colour: std::io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none(),
+            fallback_mask: Some('.'),
+        }
+    }
+}
+
+impl<'a, T> GridRender<'a, T> {
+    #[allow(dead_code)]
+    pub fn with_style(mut self, style: impl Fn((i32, i32), &T) -> Style + 'a) -> Self {
+        self.style = Box::new(style);
+        self
+    }
+
+    /// Highlights `cells` with `style`. Later overlays are drawn on top of
+    /// earlier ones.
+    pub fn with_overlay(mut self, cells: &'a GridSet, style: Style) -> Self {
+        self.overlays.push((cells, style));
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_colour(mut self, colour: bool) -> Self {
+        self.colour = colour;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_fallback_mask(mut self, mask: Option<char>) -> Self {
+        self.fallback_mask = mask;
+        self
+    }
+}
+
+impl<T> GridRender<'_, T>
+where
+    T: Display,
+{
+    fn write_cell(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        x: i32,
+        y: i32,
+        value: &T,
+    ) -> std::fmt::Result {
+        let mut overlays = self
+            .overlays
+            .iter()
+            .filter(|(cells, _)| cells.contains(x, y, ()))
+            .peekable();
+
+        if self.colour {
+            let style = overlays.fold((self.style)((x, y), value), |style, (_, overlay)| {
+                style.layer(*overlay)
+            });
+
+            style.write_start(f)?;
+            f.write_fmt(format_args!("{}", value))?;
+            return style.write_end(f);
+        }
+
+        match self.fallback_mask {
+            Some(mask) if !self.overlays.is_empty() && overlays.peek().is_none() => {
+                f.write_char(mask)
+            }
+            _ => f.write_fmt(format_args!("{}", value)),
+        }
+    }
+}
+
+impl<T> Display for GridRender<'_, T>
+where
+    T: Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self.grid.get_width() as i32;
+        let height = self.grid.get_height() as i32;
+
+        f.write_char('\n')?;
+
+        for y in 0..height {
+            for x in 0..width {
+                let value = self.grid.get(x, y).unwrap();
+                self.write_cell(f, x, y, value)?;
+
+                if x == width - 1 {
+                    f.write_char('\n')?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A small, fixed set of states that can be tracked per grid cell, e.g. a
+/// direction of travel. Each state maps to a distinct index in `0..COUNT`.
+pub trait CellState: Copy {
+    const COUNT: usize;
+
+    fn index(self) -> usize;
+}
+
+impl CellState for () {
+    const COUNT: usize = 1;
+
+    fn index(self) -> usize {
+        0
+    }
+}
+
+fn calculate_state_index<S: CellState>(
+    width: usize,
+    length: usize,
+    x: i32,
+    y: i32,
+    state: S,
+) -> Option<usize> {
+    if x < 0 || y < 0 {
+        return None;
+    }
+    if x >= width as i32 || y >= length as i32 {
+        return None;
+    }
+
+    let cell_index = y as usize * width + x as usize;
+    Some(cell_index * S::COUNT + state.index())
+}
+
+/// A set of `(position, state)` pairs over a grid, stored as a bitset so
+/// membership checks in hot loops don't hash or allocate.
+#[derive(Clone)]
+pub struct GridSet<S = ()> {
+    bits: Vec<u64>,
+    width: usize,
+    length: usize,
+    len: usize,
+    state: PhantomData<S>,
+}
+
+impl<S: CellState> GridSet<S> {
+    pub fn new(width: usize, length: usize) -> GridSet<S> {
+        let num_bits = width * length * S::COUNT;
+
+        GridSet {
+            bits: vec![0; num_bits.div_ceil(u64::BITS as usize)],
+            width,
+            length,
+            len: 0,
+            state: PhantomData,
+        }
+    }
+
+    pub fn for_grid<T>(grid: &Grid<T>) -> GridSet<S> {
+        GridSet::new(grid.get_width(), grid.get_height())
+    }
+
+    /// Returns whether the value was newly inserted, or `None` if the
+    /// position is outside the grid.
+    pub fn insert(&mut self, x: i32, y: i32, state: S) -> Option<bool> {
+        let (word, mask) = self.locate(x, y, state)?;
+
+        let is_new = self.bits[word] & mask == 0;
+        if is_new {
+            self.bits[word] |= mask;
+            self.len += 1;
+        }
+
+        Some(is_new)
+    }
+
+    pub fn contains(&self, x: i32, y: i32, state: S) -> bool {
+        match self.locate(x, y, state) {
+            Some((word, mask)) => self.bits[word] & mask != 0,
+            None => false,
+        }
+    }
+
+    /// Empties the set while keeping its allocation for reuse.
+    pub fn clear(&mut self) {
+        self.bits.fill(0);
+        self.len = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    fn locate(&self, x: i32, y: i32, state: S) -> Option<(usize, u64)> {
+        let index = calculate_state_index(self.width, self.length, x, y, state)?;
+        let word_bits = u64::BITS as usize;
+
+        Some((index / word_bits, 1 << (index % word_bits)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CellState, Colour, Grid, GridSet, ParseGridError, Style};
+    use std::str::FromStr;
+
+    #[derive(Clone, Copy)]
+    enum Side {
+        Left,
+        Right,
+    }
+
+    impl CellState for Side {
+        const COUNT: usize = 2;
+
+        fn index(self) -> usize {
+            self as usize
+        }
+    }
+
+    #[test]
+    fn test_grid_set() {
+        let mut set: GridSet<Side> = GridSet::new(9, 8);
+        assert_eq!(0, set.len());
+        assert_eq!(Some(true), set.insert(0, 0, Side::Left));
+        assert_eq!(Some(false), set.insert(0, 0, Side::Left));
+
+        // (4, 3) on the right is bit 63 and (5, 3) on the left is bit 64, so
+        // they sit either side of a word boundary. (8, 7) is the last bit.
+        assert_eq!(Some(true), set.insert(4, 3, Side::Right));
+        assert_eq!(Some(true), set.insert(8, 7, Side::Right));
+        assert!(set.contains(4, 3, Side::Right));
+        assert!(!set.contains(4, 3, Side::Left));
+        assert!(!set.contains(5, 3, Side::Left));
+        assert_eq!(3, set.len());
+
+        for (x, y) in [(-1, 0), (0, -1), (9, 0), (0, 8)] {
+            assert_eq!(None, set.insert(x, y, Side::Left));
+            assert!(!set.contains(x, y, Side::Left));
+        }
+        assert_eq!(3, set.len());
+
+        set.clear();
+        assert_eq!(0, set.len());
+        assert!(!set.contains(8, 7, Side::Right));
+    }
+
+    #[test]
+    fn test_from_str_counts_characters() {
+        let grid: Grid<char> = "ab\néü\n".parse().unwrap();
+        assert_eq!(2, grid.get_width());
+        assert_eq!(Some(&'ü'), grid.get(1, 1));
+    }
+
+    #[test]
+    fn test_from_str_delimited() {
+        let grid: Grid<u32> = Grid::from_str_delimited("1 20 300\n4 50 600\n", ' ').unwrap();
+        assert_eq!(3, grid.get_width());
+        assert_eq!(2, grid.get_height());
+        assert_eq!(Some(&600), grid.get(2, 1));
+
+        let result = Grid::<u32>::from_str_delimited("1 2 3\n4 x 6\n", ' ');
+        let Err(ParseGridError::ParseValError { location, .. }) = result else {
+            panic!("expected a ParseValError");
+        };
+        assert_eq!((2, 3), (location.line, location.column));
+    }
+
+    #[test]
+    fn test_from_str_fixed_width() {
+        let grid: Grid<u32> = Grid::from_str_fixed_width(" 123\n45 6\n", 2).unwrap();
+        assert_eq!(Some(&23), grid.get(1, 0));
+        assert_eq!(Some(&6), grid.get(1, 1));
+
+        let result = Grid::<u32>::from_str_fixed_width(" 1234\n", 2);
+        let Err(ParseGridError::IncompleteCell { location }) = result else {
+            panic!("expected an IncompleteCell");
+        };
+        assert_eq!((1, 5), (location.line, location.column));
+
+        let result = Grid::<u32>::from_str_fixed_width("12\n", 0);
+        assert!(matches!(result, Err(ParseGridError::ZeroCellWidth)));
+    }
+
+    #[test]
+    fn test_render() {
+        let grid: Grid<char> = "ab\ncd\n".parse().unwrap();
+        let mut highlighted = GridSet::for_grid(&grid);
+        highlighted.insert(1, 0, ());
+
+        let plain = grid
+            .render()
+            .with_overlay(&highlighted, Style::foreground(Colour::Red))
+            .with_colour(false);
+        assert_eq!("\n.b\n..\n", plain.to_string());
+
+        let coloured = grid
+            .render()
+            .with_style(|_, c| match c {
+                'd' => Style::background(Colour::Blue),
+                _ => Style::default(),
+            })
+            .with_overlay(&highlighted, Style::foreground(Colour::Red))
+            .with_colour(true);
+        assert_eq!(
+            "\na\x1b[31mb\x1b[0m\nc\x1b[44md\x1b[0m\n",
+            coloured.to_string()
+        );
+    }
+
+    #[test]
+    fn test_error_location() {
+        let result = Grid::<u32>::from_str_delimited("\n1 x\n", ' ');
+        assert_eq!(
+            "ParseValError: invalid digit found in string\n  --> line 2, column 3\n  |\n2 | 1 x\n  |   ^",
+            result.err().unwrap().to_string()
+        );
+
+        let result = Grid::<u32>::from_str_delimited("1 2 3\n\n4 5 x\n", ' ');
+        let Err(ParseGridError::ParseValError { location, .. }) = result else {
+            panic!("expected a ParseValError");
+        };
+        assert_eq!(3, location.line);
+        assert_eq!(5, location.column);
+        assert_eq!(
+            "  --> line 3, column 5\n  |\n3 | 4 5 x\n  |     ^",
+            location.to_string()
+        );
+
+        let result = Grid::<char>::from_str("abc\nab\n");
+        let Err(ParseGridError::DifferingRowSizes { location, .. }) = result else {
+            panic!("expected DifferingRowSizes");
+        };
+        assert_eq!(2, location.line);
+        assert_eq!(3, location.column);
+    }
+
+    /// Inputs that once made the `grid_from_str` fuzz target panic.
+    #[test]
+    fn test_fuzz_regressions() {
+        let dir = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fuzz/regressions/grid_from_str"
+        );
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let input = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+            let _ = input.parse::<Grid<char>>();
+            let _ = input.parse::<Grid<u8>>();
+        }
+
+        let grid: Grid<char> = "".parse().unwrap();
+        assert_eq!(0, grid.get_width());
+        assert_eq!(0, grid.get_height());
+        assert_eq!(None, grid.get(0, 0));
     }
 }
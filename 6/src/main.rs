@@ -1,12 +1,13 @@
-use std::collections::{BTreeSet, HashMap, HashSet};
 use std::env;
 use std::fmt::{Display, Write};
 use std::fs;
+use std::process;
 
 mod grid;
-use grid::Grid;
+use grid::{CellState, Colour, Grid, GridSet, Style};
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum MapKey {
     Guard(Direction),
     Empty,
@@ -48,7 +49,19 @@ enum ParseMapKeyError {
     InvalidChar(char),
 }
 
+impl Display for ParseMapKeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseMapKeyError::InvalidChar(c) => {
+                f.write_fmt(format_args!("got invalid character: {}", c))?;
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum Direction {
     Up,
     Down,
@@ -56,27 +69,62 @@ enum Direction {
     Right,
 }
 
+impl CellState for Direction {
+    const COUNT: usize = 4;
+
+    fn index(self) -> usize {
+        match self {
+            Direction::Up => 0,
+            Direction::Down => 1,
+            Direction::Left => 2,
+            Direction::Right => 3,
+        }
+    }
+}
+
 fn load_input() -> String {
     let args: Vec<String> = env::args().collect();
     fs::read_to_string(args.get(1).unwrap()).expect("Should have been able to read the file")
 }
 
+/// Writes the area with each looping obstruction added as JSON to the
+/// directory given as the second argument, if any.
+#[cfg(feature = "serde")]
+fn write_snapshots(area: &Grid<MapKey>, obstacle_locations: &[(i32, i32)]) {
+    let args: Vec<String> = env::args().collect();
+    let Some(snapshot_dir) = args.get(2) else {
+        return;
+    };
+
+    fs::create_dir_all(snapshot_dir).expect("should have been able to create snapshot directory");
+
+    let mut area = area.clone();
+    for (x, y) in obstacle_locations.iter().copied() {
+        let existing_tile = *area.get(x, y).unwrap();
+        area.set(x, y, MapKey::Obstruction);
+
+        let path = std::path::Path::new(snapshot_dir).join(format!("obstruction_{}_{}.json", x, y));
+        let file = fs::File::create(&path).expect("should have been able to create snapshot file");
+        serde_json::to_writer(std::io::BufWriter::new(file), &area)
+            .expect("should have been able to write snapshot");
+
+        area.set(x, y, existing_tile);
+    }
+}
+
 fn main() {
     env_logger::init();
 
     let input = load_input();
-    let area: Grid<MapKey> = input.parse().unwrap();
+    let area: Grid<MapKey> = input.parse().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
     log::debug!("{}", area);
 
     let (starting_position, guard) = area
         .iter()
-        .find(|(_, key)| {
-            if let MapKey::Guard(_) = key {
-                return true;
-            } else {
-                return false;
-            }
-        })
+        .find(|(_, key)| matches!(key, MapKey::Guard(_)))
         .unwrap();
 
     let starting_direction = match guard {
@@ -91,67 +139,81 @@ fn main() {
     visited_positions
         .iter()
         .for_each(|a| log::debug!("{:?}", a));
-    let num_unique_positions = visited_positions
-        .iter()
-        .map(|e| e.0)
-        .collect::<HashSet<(i32, i32)>>()
-        .len();
+    let mut unique_positions = GridSet::for_grid(&area);
+    visited_positions.iter().for_each(|((x, y), _)| {
+        unique_positions.insert(*x, *y, ());
+    });
+    log::debug!(
+        "{}",
+        area.render()
+            .with_overlay(&unique_positions, Style::foreground(Colour::Yellow))
+    );
+    let num_unique_positions = unique_positions.len();
     println!("{}", num_unique_positions);
 
-    let num_possible_obstacles = find_possible_obstacle_locations(&area, &visited_positions);
-    println!("{}", num_possible_obstacles);
+    let possible_obstacles = find_possible_obstacle_locations(&area, &visited_positions);
+    println!("{}", possible_obstacles.len());
+
+    #[cfg(feature = "serde")]
+    write_snapshots(&area, &possible_obstacles);
 }
 
-fn find_possible_obstacle_locations(grid: &Grid<MapKey>, visited_positions: &[GuardState]) -> u32 {
+fn find_possible_obstacle_locations(
+    grid: &Grid<MapKey>,
+    visited_positions: &[GuardState],
+) -> Vec<(i32, i32)> {
     let mut area: Grid<MapKey> = grid.clone();
-    let mut attempted_obstacle_locations = HashSet::new();
-    let mut num_possible_locations = 0;
+    let mut attempted_obstacle_locations = GridSet::for_grid(grid);
+    let mut loop_visited_positions = GridSet::for_grid(grid);
+    let mut possible_locations = Vec::new();
 
     for i in 0..visited_positions.len() - 1 {
         let starting_position = visited_positions[i];
         let potential_obstacle_location = visited_positions[i + 1].0;
 
-        if attempted_obstacle_locations.contains(&potential_obstacle_location) {
+        let (potential_x, potential_y) = potential_obstacle_location;
+        if attempted_obstacle_locations.contains(potential_x, potential_y, ()) {
             continue;
         }
 
-        let (potential_x, potential_y) = potential_obstacle_location;
         let existing_tile = grid.get(potential_x, potential_y).unwrap();
 
         area.set(potential_x, potential_y, MapKey::Obstruction);
 
         log::debug!("\n");
-        if guard_will_get_stuck_in_a_loop(&area, starting_position) {
+        if guard_will_get_stuck_in_a_loop(&area, &mut loop_visited_positions, starting_position) {
             log::debug!("Found!");
-            num_possible_locations += 1;
+            possible_locations.push(potential_obstacle_location);
         }
 
         area.set(potential_x, potential_y, *existing_tile);
 
-        attempted_obstacle_locations.insert(potential_obstacle_location);
+        attempted_obstacle_locations.insert(potential_x, potential_y, ());
     }
 
-    num_possible_locations
+    possible_locations
 }
 
+/// `visited_positions` is scratch space, cleared before use, so repeated calls
+/// can share one allocation.
 fn guard_will_get_stuck_in_a_loop(
     area: &Grid<MapKey>,
-    starting_position: ((i32, i32), Direction),
+    visited_positions: &mut GridSet<Direction>,
+    starting_position: GuardState,
 ) -> bool {
-    let mut guard_walk = GuardWalk {
+    let guard_walk = GuardWalk {
         current_position: starting_position,
-        area: &area,
+        area,
     };
 
-    let mut visited_positions = HashSet::new();
-    visited_positions.insert(starting_position);
+    visited_positions.clear();
+    let ((x, y), direction) = starting_position;
+    visited_positions.insert(x, y, direction);
 
-    while let Some(position) = guard_walk.next() {
-        if visited_positions.contains(&position) {
+    for ((x, y), direction) in guard_walk {
+        if visited_positions.insert(x, y, direction) == Some(false) {
             return true;
         }
-
-        visited_positions.insert(position);
     }
 
     false
@@ -165,12 +227,12 @@ fn get_visited_positions(
     let mut visited_positions = Vec::new();
     visited_positions.push((starting_position, starting_direction));
 
-    let mut guard_walk = GuardWalk {
+    let guard_walk = GuardWalk {
         current_position: (starting_position, starting_direction),
         area,
     };
 
-    while let Some((position, direction)) = guard_walk.next() {
+    for (position, direction) in guard_walk {
         visited_positions.push((position, direction));
     }
 
@@ -1,60 +1,412 @@
 use std::env;
+use std::fmt::Display;
 use std::fs;
 use std::num::ParseIntError;
+use std::ops::ControlFlow;
+use std::process;
 use std::str::FromStr;
 
+mod number;
+mod operator;
+use number::Number;
+use operator::{operator_from_name, Operands, Operator, Precedence};
+
 #[derive(Debug, Clone)]
-struct Equation {
-    test_value: u64,
-    numbers: Vec<u64>,
+struct Equation<N> {
+    test_value: N,
+    numbers: Vec<N>,
+}
+
+/// Where and why a line isn't an equation.
+#[derive(Debug)]
+struct ParseEquationError {
+    /// One-based, counted in characters.
+    column: usize,
+    /// The text at `column` that couldn't be parsed.
+    token: String,
+    kind: ParseEquationErrorKind,
 }
 
 #[derive(Debug)]
-enum ParseEquationError {
-    IncorrectFormat,
-    ParseTestValueError(ParseIntError),
-    ParseNumbersError(ParseIntError),
+enum ParseEquationErrorKind {
+    MissingSeparator,
+    InvalidTestValue(ParseIntError),
+    InvalidNumber(ParseIntError),
 }
 
-impl FromStr for Equation {
+impl<N: Number> FromStr for Equation<N> {
     type Err = ParseEquationError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let splits = s.splitn(2, ": ").collect::<Vec<&str>>();
-        if splits.len() != 2 {
-            return Err(ParseEquationError::IncorrectFormat);
-        }
+        let error = |offset: usize, token: &str, kind| ParseEquationError {
+            column: s[..offset].chars().count() + 1,
+            token: token.to_string(),
+            kind,
+        };
 
-        let test_value = splits[0]
-            .parse()
-            .map_err(ParseEquationError::ParseTestValueError)?;
+        let separator = s.find([':', ' ']).unwrap_or(s.len());
+        let test_value_token = &s[..separator];
+        let test_value = test_value_token.parse().map_err(|err| {
+            error(
+                0,
+                test_value_token,
+                ParseEquationErrorKind::InvalidTestValue(err),
+            )
+        })?;
 
-        let numbers = splits[1]
-            .split(' ')
-            .map(|v| v.parse())
-            .collect::<Result<Vec<u64>, ParseIntError>>()
-            .map_err(ParseEquationError::ParseNumbersError)?;
+        let Some(numbers_token) = s[separator..].strip_prefix(": ") else {
+            let found = s[separator..].chars().take(2).collect::<String>();
+            return Err(error(
+                separator,
+                &found,
+                ParseEquationErrorKind::MissingSeparator,
+            ));
+        };
+
+        let mut offset = separator + 2;
+        let mut numbers = Vec::new();
+        for token in numbers_token.split(' ') {
+            let number = token
+                .parse()
+                .map_err(|err| error(offset, token, ParseEquationErrorKind::InvalidNumber(err)))?;
+            numbers.push(number);
+            offset += token.len() + 1;
+        }
 
-        return Ok(Equation {
+        Ok(Equation {
             test_value,
             numbers,
-        });
+        })
+    }
+}
+
+impl<N: Number> Display for Equation<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}:", self.test_value))?;
+        for number in self.numbers.iter() {
+            f.write_fmt(format_args!(" {}", number))?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for ParseEquationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("column {}: ", self.column))?;
+        match &self.kind {
+            ParseEquationErrorKind::MissingSeparator if self.token.is_empty() => {
+                f.write_str("expected \": \" after the test value, found the end of the line")
+            }
+            ParseEquationErrorKind::MissingSeparator => f.write_fmt(format_args!(
+                "expected \": \" after the test value, found {:?}",
+                self.token
+            )),
+            ParseEquationErrorKind::InvalidTestValue(error) => f.write_fmt(format_args!(
+                "invalid test value {:?}: {}",
+                self.token, error
+            )),
+            ParseEquationErrorKind::InvalidNumber(error) => {
+                f.write_fmt(format_args!("invalid number {:?}: {}", self.token, error))
+            }
+        }
     }
 }
 
 #[derive(Debug)]
 enum BridgeRepairError {
     ParseError {
+        /// One-based.
         line_number: u32,
         error: ParseEquationError,
     },
+    /// The sum of the test values doesn't fit in the chosen `Width`.
+    TotalOverflow(Width),
+}
+
+impl Display for BridgeRepairError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BridgeRepairError::ParseError { line_number, error } => {
+                f.write_fmt(format_args!("line {}, {}", line_number, error))
+            }
+            BridgeRepairError::TotalOverflow(width) => f.write_fmt(format_args!(
+                "total calibration result doesn't fit in {} bits, try --width 128",
+                width.bits()
+            )),
+        }
+    }
+}
+
+const PART_1_OPERATORS: &str = "add,multiply";
+const PART_2_OPERATORS: &str = "add,multiply,concat";
+
+/// An equation with the operators that solve it, shown as an expression such
+/// as `81 + 40 * 27 = 3267`.
+struct Solution<'a, N: Number> {
+    equation: &'a Equation<N>,
+    operators: Vec<&'a dyn Operator<N>>,
+}
+
+impl<N: Number> Display for Solution<'_, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut numbers = self.equation.numbers.iter();
+        if let Some(first) = numbers.next() {
+            f.write_fmt(format_args!("{}", first))?;
+        }
+
+        for (operator, number) in self.operators.iter().zip(numbers) {
+            f.write_fmt(format_args!(" {} {}", operator.symbol(), number))?;
+        }
+
+        f.write_fmt(format_args!(" = {}", self.equation.test_value))
+    }
+}
+
+/// How equations are searched for operator sequences that solve them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Solver {
+    /// Applies operators left to right from the first number, giving up once
+    /// the value passes the test value.
+    Forward,
+    /// Undoes operators right to left from the test value, only following
+    /// operators that could have produced it.
+    Backward,
+}
+
+/// How an operator sequence is evaluated to compare with the test value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Evaluation {
+    /// Applies operators in turn, ignoring the usual maths rules.
+    LeftToRight,
+    /// Applies operators that bind more tightly first, such as multiplication
+    /// before addition, and operators that bind equally left to right.
+    Precedence(ConcatenationPrecedence),
+}
+
+/// Where concatenation binds relative to the other operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConcatenationPrecedence {
+    Highest,
+    Lowest,
+}
+
+impl ConcatenationPrecedence {
+    /// Higher values bind more tightly, and nothing binds less than zero.
+    fn binding_power(self, precedence: Precedence) -> u8 {
+        match (precedence, self) {
+            (Precedence::Concatenation, ConcatenationPrecedence::Lowest) => 0,
+            (Precedence::Bitwise, _) => 1,
+            (Precedence::Additive, _) => 2,
+            (Precedence::Multiplicative, _) => 3,
+            (Precedence::Exponent, _) => 4,
+            (Precedence::Concatenation, ConcatenationPrecedence::Highest) => 5,
+        }
+    }
+}
+
+/// The unsigned integer type equations are solved in. Anything that doesn't
+/// fit, from a number in the input to an intermediate value, is treated as
+/// unreachable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Width {
+    U64,
+    U128,
+}
+
+impl Width {
+    fn bits(&self) -> u32 {
+        match self {
+            Width::U64 => u64::BITS,
+            Width::U128 => u128::BITS,
+        }
+    }
+}
+
+/// What happens to lines that aren't equations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InvalidLines {
+    Abort,
+    /// Prints a warning and carries on without the line.
+    Skip,
 }
 
-#[derive(Clone, Copy)]
-enum Operator {
-    Add,
-    Multiply,
-    Concatenation,
+/// Which solutions `--show` prints for each solvable equation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShowSolutions {
+    First,
+    All,
+    Count,
+}
+
+#[derive(Debug)]
+enum ParseOptionsError {
+    UnknownFlag(String),
+    MissingValue(String),
+    InvalidValue { flag: String, value: String },
+    UnknownOperator(String),
+    ConflictingFlags(&'static str, &'static str),
+}
+
+impl Display for ParseOptionsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseOptionsError::UnknownFlag(flag) => {
+                f.write_fmt(format_args!("unknown flag: {}", flag))
+            }
+            ParseOptionsError::MissingValue(flag) => {
+                f.write_fmt(format_args!("missing value for {}", flag))
+            }
+            ParseOptionsError::InvalidValue { flag, value } => {
+                f.write_fmt(format_args!("invalid value for {}: {}", flag, value))
+            }
+            ParseOptionsError::UnknownOperator(name) => {
+                f.write_fmt(format_args!("unknown operator: {}", name))
+            }
+            ParseOptionsError::ConflictingFlags(a, b) => {
+                f.write_fmt(format_args!("{} cannot be used with {}", a, b))
+            }
+        }
+    }
+}
+
+/// Which operators the equations may use, how they are solved and evaluated,
+/// and what is printed for them.
+#[derive(Debug)]
+struct Options {
+    show: Option<ShowSolutions>,
+    /// Operator names that replace the operators of both parts when set.
+    operators: Option<String>,
+    solver: Solver,
+    evaluation: Evaluation,
+    width: Width,
+    invalid_lines: InvalidLines,
+}
+
+impl Options {
+    /// Builds options from `--show first|all|count`, which prints solutions
+    /// instead of totals, and `--part 1|2` or `--operators NAME,...`, which
+    /// pick the operators to use instead of running both parts. See
+    /// `operator_from_name` for the names. `--solver forward|backward` picks
+    /// the `Solver` and `--width 64|128` the `Width`. `--evaluation
+    /// left-to-right|precedence` picks the `Evaluation`, with concatenation
+    /// binding as set by `--concat-precedence highest|lowest`, and only works
+    /// with the forward solver. So does the `divide` operator. `--invalid-lines
+    /// abort|skip` picks what happens to `InvalidLines`.
+    fn from_args(args: &[String]) -> Result<Options, ParseOptionsError> {
+        let mut options = Options {
+            show: None,
+            operators: None,
+            solver: Solver::Forward,
+            evaluation: Evaluation::LeftToRight,
+            width: Width::U64,
+            invalid_lines: InvalidLines::Abort,
+        };
+        let mut precedence = false;
+        let mut concatenation = ConcatenationPrecedence::Highest;
+
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| ParseOptionsError::MissingValue(flag.clone()))?;
+            let invalid_value = || ParseOptionsError::InvalidValue {
+                flag: flag.clone(),
+                value: value.clone(),
+            };
+
+            match flag.as_str() {
+                "--show" => {
+                    options.show = Some(match value.as_str() {
+                        "first" => ShowSolutions::First,
+                        "all" => ShowSolutions::All,
+                        "count" => ShowSolutions::Count,
+                        _ => return Err(invalid_value()),
+                    });
+                }
+                "--part" => {
+                    let names = match value.as_str() {
+                        "1" => PART_1_OPERATORS,
+                        "2" => PART_2_OPERATORS,
+                        _ => return Err(invalid_value()),
+                    };
+                    options.operators = Some(names.to_string());
+                }
+                "--operators" => {
+                    parse_operators::<u64>(value)?;
+                    options.operators = Some(value.clone());
+                }
+                "--solver" => {
+                    options.solver = match value.as_str() {
+                        "forward" => Solver::Forward,
+                        "backward" => Solver::Backward,
+                        _ => return Err(invalid_value()),
+                    };
+                }
+                "--evaluation" => {
+                    precedence = match value.as_str() {
+                        "left-to-right" => false,
+                        "precedence" => true,
+                        _ => return Err(invalid_value()),
+                    };
+                }
+                "--concat-precedence" => {
+                    concatenation = match value.as_str() {
+                        "highest" => ConcatenationPrecedence::Highest,
+                        "lowest" => ConcatenationPrecedence::Lowest,
+                        _ => return Err(invalid_value()),
+                    };
+                }
+                "--invalid-lines" => {
+                    options.invalid_lines = match value.as_str() {
+                        "abort" => InvalidLines::Abort,
+                        "skip" => InvalidLines::Skip,
+                        _ => return Err(invalid_value()),
+                    };
+                }
+                "--width" => {
+                    options.width = match value.as_str() {
+                        "64" => Width::U64,
+                        "128" => Width::U128,
+                        _ => return Err(invalid_value()),
+                    };
+                }
+                _ => return Err(ParseOptionsError::UnknownFlag(flag.clone())),
+            }
+        }
+
+        if let (Solver::Backward, Some(names)) = (options.solver, &options.operators) {
+            let operators = parse_operators::<u64>(names)?;
+            if operators.iter().any(|operator| !operator.can_unapply()) {
+                return Err(ParseOptionsError::ConflictingFlags(
+                    "--operators divide",
+                    "--solver backward",
+                ));
+            }
+        }
+
+        if precedence {
+            if options.solver == Solver::Backward {
+                return Err(ParseOptionsError::ConflictingFlags(
+                    "--evaluation precedence",
+                    "--solver backward",
+                ));
+            }
+
+            options.evaluation = Evaluation::Precedence(concatenation);
+        }
+
+        Ok(options)
+    }
+}
+
+fn parse_operators<N: Number>(names: &str) -> Result<Vec<Box<dyn Operator<N>>>, ParseOptionsError> {
+    names
+        .split(',')
+        .map(|name| {
+            operator_from_name(name)
+                .ok_or_else(|| ParseOptionsError::UnknownOperator(name.to_string()))
+        })
+        .collect()
 }
 
 fn load_input() -> String {
@@ -62,122 +414,530 @@ fn load_input() -> String {
     fs::read_to_string(args.get(1).unwrap()).expect("should have been able to read the file")
 }
 
-fn main() -> Result<(), BridgeRepairError> {
+fn main() {
     env_logger::init();
 
+    let args: Vec<String> = env::args().collect();
+    let options = Options::from_args(args.get(2..).unwrap_or_default()).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        eprintln!("usage: seven <input> [--show first|all|count] [--part 1|2 | --operators NAME,...] [--solver forward|backward] [--evaluation left-to-right|precedence] [--concat-precedence highest|lowest] [--width 64|128] [--invalid-lines abort|skip]");
+        process::exit(1);
+    });
+
     let input = load_input();
-    let equations = input
-        .split('\n')
-        .enumerate()
-        .filter(|(_, line)| !line.is_empty())
-        .map(|(line_number, line)| {
-            line.parse().map_err(|err| BridgeRepairError::ParseError {
-                line_number: line_number as u32,
-                error: err,
-            })
-        })
-        .collect::<Result<Vec<Equation>, BridgeRepairError>>()?;
+    let result = match options.width {
+        Width::U64 => run::<u64>(&options, &input),
+        Width::U128 => run::<u128>(&options, &input),
+    };
+
+    result.unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+}
+
+/// Solves the equations in `input` with values of type `N`, which should
+/// match `options.width`.
+fn run<N: Number>(options: &Options, input: &str) -> Result<(), BridgeRepairError> {
+    let mut equations: Vec<Equation<N>> = Vec::new();
+    for (line_number, line) in input.split('\n').enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+
+        match line.parse() {
+            Ok(equation) => equations.push(equation),
+            Err(err) => {
+                let err = BridgeRepairError::ParseError {
+                    line_number: line_number as u32 + 1,
+                    error: err,
+                };
+                match options.invalid_lines {
+                    InvalidLines::Abort => return Err(err),
+                    InvalidLines::Skip => eprintln!("warning: skipping {}", err),
+                }
+            }
+        }
+    }
 
     equations.iter().for_each(|e| log::debug!("{:?}", e));
 
-    const PART_1_OPERATORS: [Operator; 2] = [Operator::Add, Operator::Multiply];
-    let total_calibration_result =
-        calculate_total_calibration_result(&PART_1_OPERATORS, &equations);
-    println!("{}", total_calibration_result);
+    // The names were checked when the options were parsed.
+    let load_operators = |names: &str| parse_operators::<N>(names).unwrap();
+    let total = |operators: &[Box<dyn Operator<N>>]| {
+        let operators: Vec<&dyn Operator<N>> = operators.iter().map(Box::as_ref).collect();
+        calculate_total_calibration_result(
+            options.solver,
+            options.evaluation,
+            &operators,
+            &equations,
+        )
+        .ok_or(BridgeRepairError::TotalOverflow(options.width))
+    };
 
-    const PART_2_OPERATORS: [Operator; 3] =
-        [Operator::Add, Operator::Multiply, Operator::Concatenation];
-    let total_calibration_result =
-        calculate_total_calibration_result(&PART_2_OPERATORS, &equations);
-    println!("{}", total_calibration_result);
+    if let Some(show) = options.show {
+        let names = options.operators.as_deref().unwrap_or(PART_2_OPERATORS);
+        let operators = load_operators(names);
+        let operators: Vec<&dyn Operator<N>> = operators.iter().map(Box::as_ref).collect();
+        print_solutions(
+            show,
+            options.solver,
+            options.evaluation,
+            &operators,
+            &equations,
+        );
+        return Ok(());
+    }
+
+    if let Some(names) = &options.operators {
+        println!("{}", total(&load_operators(names))?);
+        return Ok(());
+    }
+
+    println!("{}", total(&load_operators(PART_1_OPERATORS))?);
+    println!("{}", total(&load_operators(PART_2_OPERATORS))?);
 
     Ok(())
 }
 
-fn calculate_total_calibration_result(operators: &[Operator], equations: &[Equation]) -> u64 {
+fn print_solutions<N: Number>(
+    show: ShowSolutions,
+    solver: Solver,
+    evaluation: Evaluation,
+    operators: &[&dyn Operator<N>],
+    equations: &[Equation<N>],
+) {
+    for equation in equations.iter() {
+        match show {
+            ShowSolutions::First => {
+                if let Some(operators) = find_solution(solver, evaluation, operators, equation) {
+                    println!(
+                        "{}",
+                        Solution {
+                            equation,
+                            operators
+                        }
+                    );
+                }
+            }
+            ShowSolutions::All => {
+                for operators in find_all_solutions(solver, evaluation, operators, equation) {
+                    println!(
+                        "{}",
+                        Solution {
+                            equation,
+                            operators
+                        }
+                    );
+                }
+            }
+            ShowSolutions::Count => {
+                let count = count_solutions(solver, evaluation, operators, equation);
+                if count > 0 {
+                    let noun = if count == 1 { "solution" } else { "solutions" };
+                    println!("{} ({} {})", equation, count, noun);
+                }
+            }
+        }
+    }
+}
+
+fn calculate_total_calibration_result<N: Number>(
+    solver: Solver,
+    evaluation: Evaluation,
+    operators: &[&dyn Operator<N>],
+    equations: &[Equation<N>],
+) -> Option<N> {
     let possible_equations = equations
         .iter()
-        .filter(|equation| is_possible(operators, equation))
-        .collect::<Vec<&Equation>>();
+        .filter(|equation| is_possible(solver, evaluation, operators, equation))
+        .collect::<Vec<&Equation<N>>>();
 
     log::debug!("Num possible: {}", possible_equations.len());
     equations.iter().for_each(|e| log::debug!("{:?}", e));
 
-    let total_calibration_result: u64 = possible_equations
+    possible_equations
         .iter()
-        .map(|equation| equation.test_value)
-        .sum();
+        .try_fold(N::from(0), |total, equation| {
+            total.checked_add(equation.test_value)
+        })
+}
+
+fn is_possible<N: Number>(
+    solver: Solver,
+    evaluation: Evaluation,
+    operators: &[&dyn Operator<N>],
+    equation: &Equation<N>,
+) -> bool {
+    find_solution(solver, evaluation, operators, equation).is_some()
+}
+
+/// Returns the first operator sequence `solver` finds that makes `equation`
+/// true.
+fn find_solution<'a, N: Number>(
+    solver: Solver,
+    evaluation: Evaluation,
+    operators: &[&'a dyn Operator<N>],
+    equation: &Equation<N>,
+) -> Option<Vec<&'a dyn Operator<N>>> {
+    let mut solution = None;
+    solve(solver, evaluation, operators, equation, &mut |path| {
+        solution = Some(path.to_vec());
+        ControlFlow::Break(())
+    });
 
-    total_calibration_result
+    solution
 }
 
-fn is_possible(operators: &[Operator], equation: &Equation) -> bool {
-    return is_possible_recursive(operators, equation.test_value, None, &equation.numbers);
+fn find_all_solutions<'a, N: Number>(
+    solver: Solver,
+    evaluation: Evaluation,
+    operators: &[&'a dyn Operator<N>],
+    equation: &Equation<N>,
+) -> Vec<Vec<&'a dyn Operator<N>>> {
+    let mut solutions = Vec::new();
+    solve(solver, evaluation, operators, equation, &mut |path| {
+        solutions.push(path.to_vec());
+        ControlFlow::Continue(())
+    });
+
+    solutions
 }
 
-fn is_possible_recursive(
-    operators: &[Operator],
-    test_value: u64,
-    current_value: Option<u64>,
-    numbers: &[u64],
-) -> bool {
+fn count_solutions<N: Number>(
+    solver: Solver,
+    evaluation: Evaluation,
+    operators: &[&dyn Operator<N>],
+    equation: &Equation<N>,
+) -> usize {
+    let mut count = 0;
+    solve(solver, evaluation, operators, equation, &mut |_| {
+        count += 1;
+        ControlFlow::Continue(())
+    });
+
+    count
+}
+
+fn solve<'a, N: Number>(
+    solver: Solver,
+    evaluation: Evaluation,
+    operators: &[&'a dyn Operator<N>],
+    equation: &Equation<N>,
+    on_solution: &mut impl FnMut(&[&'a dyn Operator<N>]) -> ControlFlow<()>,
+) {
+    let mut path = Vec::with_capacity(equation.numbers.len());
+    let _ = match (solver, evaluation) {
+        (Solver::Forward, Evaluation::Precedence(concatenation)) => {
+            let Some((first, rest)) = equation.numbers.split_first() else {
+                return;
+            };
+
+            solve_precedence_recursive(
+                operators,
+                concatenation,
+                equation.test_value,
+                &[],
+                *first,
+                rest,
+                &mut path,
+                on_solution,
+            )
+        }
+        (Solver::Forward, Evaluation::LeftToRight) => solve_recursive(
+            operators,
+            equation.numbers.iter().skip(1).all(|number| {
+                operators
+                    .iter()
+                    .all(|operator| operator.is_monotonic(*number))
+            }),
+            equation.test_value,
+            None,
+            &equation.numbers,
+            &mut path,
+            on_solution,
+        ),
+        (Solver::Backward, _) => solve_backward_recursive(
+            operators,
+            equation.test_value,
+            &equation.numbers,
+            &mut path,
+            on_solution,
+        ),
+    };
+}
+
+/// Tries every operator sequence depth first, with `path` holding the
+/// operators applied so far, and calls `on_solution` for each one that
+/// reaches `test_value` until it breaks. With `prune`, which is only valid
+/// when every operator is monotonic for every number, values past
+/// `test_value` are abandoned.
+fn solve_recursive<'a, N: Number>(
+    operators: &[&'a dyn Operator<N>],
+    prune: bool,
+    test_value: N,
+    current_value: Option<N>,
+    numbers: &[N],
+    path: &mut Vec<&'a dyn Operator<N>>,
+    on_solution: &mut impl FnMut(&[&'a dyn Operator<N>]) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    let Some(current) = current_value else {
+        if numbers.is_empty() {
+            return ControlFlow::Continue(());
+        }
+
+        return solve_recursive(
+            operators,
+            prune,
+            test_value,
+            Some(numbers[0]),
+            &numbers[1..],
+            path,
+            on_solution,
+        );
+    };
+
     if numbers.is_empty() {
-        if current_value == None {
-            return false;
+        if current == test_value {
+            return on_solution(path);
         }
 
-        return test_value == current_value.unwrap();
+        return ControlFlow::Continue(());
     }
 
-    if current_value == None {
-        return is_possible_recursive(operators, test_value, Some(numbers[0]), &numbers[1..]);
+    if prune && current > test_value {
+        return ControlFlow::Continue(());
     }
 
-    let current = current_value.unwrap();
-    if current > test_value {
-        return false;
+    for op in operators.iter() {
+        let Some(new_value) = op.apply(current, numbers[0]) else {
+            continue;
+        };
+
+        path.push(*op);
+        let flow = solve_recursive(
+            operators,
+            prune,
+            test_value,
+            Some(new_value),
+            &numbers[1..],
+            path,
+            on_solution,
+        );
+        path.pop();
+
+        if flow.is_break() {
+            return flow;
+        }
     }
 
-    operators.into_iter().any(|op| {
-        let new_value = apply(*op, current, numbers[0]);
-        if new_value == None {
-            return false;
+    ControlFlow::Continue(())
+}
+
+/// Tries every operator sequence depth first like `solve_recursive`, but
+/// evaluates with precedence. `pending` holds the left operands still waiting
+/// on their operators, which bind less tightly the further down they are, and
+/// `current` is the operand after the last of them.
+#[allow(clippy::too_many_arguments)]
+fn solve_precedence_recursive<'a, N: Number>(
+    operators: &[&'a dyn Operator<N>],
+    concatenation: ConcatenationPrecedence,
+    test_value: N,
+    pending: &[(N, &'a dyn Operator<N>)],
+    current: N,
+    numbers: &[N],
+    path: &mut Vec<&'a dyn Operator<N>>,
+    on_solution: &mut impl FnMut(&[&'a dyn Operator<N>]) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    let Some((next, rest)) = numbers.split_first() else {
+        let mut pending = pending.to_vec();
+        if reduce(&mut pending, current, 0, concatenation) == Some(test_value) {
+            return on_solution(path);
         }
 
-        return is_possible_recursive(
+        return ControlFlow::Continue(());
+    };
+
+    for op in operators.iter() {
+        // A right associative operator leaves equally tight ones pending, so
+        // they apply to its result.
+        let mut binding_power = concatenation.binding_power(op.precedence());
+        if op.precedence().is_right_associative() {
+            binding_power += 1;
+        }
+        let mut pending = pending.to_vec();
+        let Some(left) = reduce(&mut pending, current, binding_power, concatenation) else {
+            continue;
+        };
+        pending.push((left, *op));
+
+        path.push(*op);
+        let flow = solve_precedence_recursive(
             operators,
+            concatenation,
             test_value,
-            Some(new_value.unwrap()),
-            &numbers[1..],
+            &pending,
+            *next,
+            rest,
+            path,
+            on_solution,
         );
-    })
+        path.pop();
+
+        if flow.is_break() {
+            return flow;
+        }
+    }
+
+    ControlFlow::Continue(())
 }
 
-fn apply(op: Operator, a: u64, b: u64) -> Option<u64> {
-    match op {
-        Operator::Add => Some(a + b),
-        Operator::Multiply => Some(a * b),
-        Operator::Concatenation => {
-            if a == 0 {
-                return None;
+/// Applies the operators in `pending` that bind at least as tightly as
+/// `binding_power` to `current`, from the last, returning the result or
+/// `None` if an operator has none.
+fn reduce<N: Number>(
+    pending: &mut Vec<(N, &dyn Operator<N>)>,
+    mut current: N,
+    binding_power: u8,
+    concatenation: ConcatenationPrecedence,
+) -> Option<N> {
+    while let Some((left, op)) = pending.last() {
+        if concatenation.binding_power(op.precedence()) < binding_power {
+            break;
+        }
+
+        current = op.apply(*left, current)?;
+        pending.pop();
+    }
+
+    Some(current)
+}
+
+/// Undoes the operator before the last number, so `path` holds operators
+/// from last to first, and calls `on_solution` with each sequence that
+/// reaches `target` from the first number until it breaks.
+fn solve_backward_recursive<'a, N: Number>(
+    operators: &[&'a dyn Operator<N>],
+    target: N,
+    numbers: &[N],
+    path: &mut Vec<&'a dyn Operator<N>>,
+    on_solution: &mut impl FnMut(&[&'a dyn Operator<N>]) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    let Some((last, rest)) = numbers.split_last() else {
+        return ControlFlow::Continue(());
+    };
+
+    if rest.is_empty() {
+        if *last == target {
+            let operators: Vec<&dyn Operator<N>> = path.iter().rev().copied().collect();
+            return on_solution(&operators);
+        }
+
+        return ControlFlow::Continue(());
+    }
+
+    for op in operators.iter() {
+        let range = match op.unapply(target, *last) {
+            Some(Operands::Range(range)) => range,
+            Some(Operands::Any) => {
+                let (first, rest) = rest.split_first().unwrap();
+                path.push(*op);
+                let flow = solve_any_recursive(
+                    operators,
+                    *first,
+                    rest,
+                    &mut Vec::new(),
+                    path,
+                    on_solution,
+                );
+                path.pop();
+
+                if flow.is_break() {
+                    return flow;
+                }
+                continue;
+            }
+            None => continue,
+        };
+
+        // Stepping through a `RangeInclusive<N>` needs the unstable `Step`.
+        let (mut previous, end) = range.into_inner();
+        loop {
+            path.push(*op);
+            let flow = solve_backward_recursive(operators, previous, rest, path, on_solution);
+            path.pop();
+
+            if flow.is_break() {
+                return flow;
             }
 
-            let num_digits = b.ilog10() + 1;
-            log::trace!("Num Digits of {}: {}", a, num_digits);
-            const TEN: u64 = 10;
-            a.checked_mul(TEN.checked_pow(num_digits)?)?.checked_add(b)
+            if previous == end {
+                break;
+            }
+            previous = previous.checked_add(N::from(1)).unwrap();
+        }
+    }
+
+    ControlFlow::Continue(())
+}
+
+/// Calls `on_solution` with each operator sequence that `numbers` can be
+/// evaluated with from `current`, followed by `undone`, which holds the
+/// operators the backward solver undid from last to first, until it breaks.
+/// This is how the backward solver goes on once any value will do.
+fn solve_any_recursive<'a, N: Number>(
+    operators: &[&'a dyn Operator<N>],
+    current: N,
+    numbers: &[N],
+    path: &mut Vec<&'a dyn Operator<N>>,
+    undone: &[&'a dyn Operator<N>],
+    on_solution: &mut impl FnMut(&[&'a dyn Operator<N>]) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    let Some((next, rest)) = numbers.split_first() else {
+        let operators: Vec<&dyn Operator<N>> =
+            path.iter().chain(undone.iter().rev()).copied().collect();
+        return on_solution(&operators);
+    };
+
+    for op in operators.iter() {
+        let Some(new_value) = op.apply(current, *next) else {
+            continue;
+        };
+
+        path.push(*op);
+        let flow = solve_any_recursive(operators, new_value, rest, path, undone, on_solution);
+        path.pop();
+
+        if flow.is_break() {
+            return flow;
         }
     }
+
+    ControlFlow::Continue(())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{apply, is_possible, Equation, Operator};
+    use crate::operator::{
+        operator_from_name, Add, Concatenation, Divide, Multiply, Operands, Operator, Power,
+        Subtract, Xor,
+    };
+    use crate::{
+        count_solutions, find_all_solutions, find_solution, is_possible, ConcatenationPrecedence,
+        Equation, Evaluation, Options, ParseOptionsError, Solution, Solver,
+    };
+    use proptest::prelude::*;
+
+    const CONCATENATION: Concatenation = Concatenation { base: 10 };
+
+    fn symbols(operators: &[&dyn Operator<u64>]) -> Vec<String> {
+        operators.iter().map(|operator| operator.symbol()).collect()
+    }
 
     #[test]
     fn test_apply() {
         let _ = env_logger::try_init();
-        let actual = apply(Operator::Concatenation, 12345, 6789);
+        let actual = CONCATENATION.apply(12345u64, 6789);
         let expected = Some(123456789);
 
         assert_eq!(expected, actual);
@@ -186,9 +946,11 @@ mod tests {
     #[test]
     fn test_concat() {
         let actual = is_possible(
-            &[Operator::Add, Operator::Multiply, Operator::Concatenation],
+            Solver::Forward,
+            Evaluation::LeftToRight,
+            &[&Add, &Multiply, &CONCATENATION],
             &Equation {
-                test_value: 123456789101112,
+                test_value: 123456789101112u64,
                 numbers: vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12],
             },
         );
@@ -198,9 +960,298 @@ mod tests {
 
     #[test]
     fn test_concat_2() {
-        let actual = apply(Operator::Concatenation, 1, 2);
+        let actual = CONCATENATION.apply(1u64, 2);
         let expected = Some(12);
 
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn test_solutions() {
+        let operators: [&dyn Operator<u64>; 2] = [&Add, &Multiply];
+        let equation: Equation<u64> = "3267: 81 40 27".parse().unwrap();
+
+        let first = find_solution(
+            Solver::Forward,
+            Evaluation::LeftToRight,
+            &operators,
+            &equation,
+        )
+        .unwrap();
+        assert_eq!(vec!["+", "*"], symbols(&first));
+        assert_eq!(
+            "81 + 40 * 27 = 3267",
+            Solution {
+                equation: &equation,
+                operators: first,
+            }
+            .to_string()
+        );
+
+        let all = find_all_solutions(
+            Solver::Forward,
+            Evaluation::LeftToRight,
+            &operators,
+            &equation,
+        );
+        assert_eq!(
+            vec![vec!["+", "*"], vec!["*", "+"]],
+            all.iter()
+                .map(|solution| symbols(solution))
+                .collect::<Vec<Vec<String>>>()
+        );
+        assert_eq!(
+            2,
+            count_solutions(
+                Solver::Forward,
+                Evaluation::LeftToRight,
+                &operators,
+                &equation
+            )
+        );
+
+        let equation: Equation<u64> = "83: 17 5".parse().unwrap();
+        assert!(find_solution(
+            Solver::Forward,
+            Evaluation::LeftToRight,
+            &operators,
+            &equation
+        )
+        .is_none());
+        assert_eq!(
+            0,
+            count_solutions(
+                Solver::Forward,
+                Evaluation::LeftToRight,
+                &operators,
+                &equation
+            )
+        );
+    }
+
+    #[test]
+    fn test_unapply() {
+        assert_eq!(
+            Some(Operands::Range(15..=15)),
+            CONCATENATION.unapply(156u64, 6)
+        );
+        assert_eq!(
+            Some(Operands::Range(1..=1)),
+            CONCATENATION.unapply(10u64, 0)
+        );
+        assert_eq!(None, CONCATENATION.unapply(6u64, 6));
+        assert_eq!(None, CONCATENATION.unapply(156u64, 5));
+        assert_eq!(Some(Operands::Range(13..=13)), Multiply.unapply(156u64, 12));
+        assert_eq!(None, Multiply.unapply(156u64, 7));
+        assert_eq!(Some(Operands::Any), Multiply.unapply(0u64, 0));
+        assert_eq!(None, Multiply.unapply(5u64, 0));
+        assert_eq!(None, Add.unapply(5u64, 6));
+        assert_eq!(Some(Operands::Range(21..=23)), Divide.unapply(7u64, 3));
+        assert_eq!(Some(Operands::Range(3..=3)), Power.unapply(81u64, 4));
+        assert_eq!(None, Power.unapply(80u64, 4));
+    }
+
+    #[test]
+    fn test_operator_from_name() {
+        let binary = operator_from_name::<u64>("concat2").unwrap();
+        assert_eq!(Some(0b1011), binary.apply(0b10, 0b11));
+        assert_eq!("||2", binary.symbol());
+
+        assert!(operator_from_name::<u64>("concat1").is_none());
+        assert!(operator_from_name::<u64>("modulo").is_none());
+
+        // Subtraction isn't monotonic, so `20 + 5 - 9` must not be pruned
+        // once it passes 16.
+        let equation: Equation<u64> = "16: 20 5 9".parse().unwrap();
+        let operators: [&dyn Operator<u64>; 2] = [&Add, &Subtract];
+        assert!(is_possible(
+            Solver::Forward,
+            Evaluation::LeftToRight,
+            &operators,
+            &equation
+        ));
+
+        // Multiplying by zero undoes passing the test value, and any value
+        // multiplied by zero is zero.
+        let equation: Equation<u64> = "0: 5 0".parse().unwrap();
+        let operators: [&dyn Operator<u64>; 2] = [&Add, &Multiply];
+        for solver in [Solver::Forward, Solver::Backward] {
+            let solutions =
+                find_all_solutions(solver, Evaluation::LeftToRight, &operators, &equation);
+            assert_eq!(
+                vec![vec!["*"]],
+                solutions
+                    .iter()
+                    .map(|solution| symbols(solution))
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_options_from_args() {
+        let args =
+            |args: &[&str]| -> Vec<String> { args.iter().map(|arg| arg.to_string()).collect() };
+
+        // Undoing a division by a large number has too many answers to try.
+        assert!(matches!(
+            Options::from_args(&args(&[
+                "--operators",
+                "add,divide",
+                "--solver",
+                "backward"
+            ])),
+            Err(ParseOptionsError::ConflictingFlags(..))
+        ));
+        assert!(Options::from_args(&args(&["--operators", "add,divide"])).is_ok());
+    }
+
+    #[test]
+    fn test_u128() {
+        let operators: [&dyn Operator<u128>; 3] = [&Add, &Multiply, &CONCATENATION];
+        let input = "36893488147419103232: 18446744073709551616 2";
+        assert!(input.parse::<Equation<u64>>().is_err());
+
+        let equation: Equation<u128> = input.parse().unwrap();
+        for solver in [Solver::Forward, Solver::Backward] {
+            assert!(is_possible(
+                solver,
+                Evaluation::LeftToRight,
+                &operators,
+                &equation
+            ));
+        }
+
+        // Concatenating past `u64::MAX` is reachable rather than pruned.
+        let equation: Equation<u128> = "18446744073709551616123: 18446744073709551616 123"
+            .parse()
+            .unwrap();
+        assert!(is_possible(
+            Solver::Forward,
+            Evaluation::LeftToRight,
+            &operators,
+            &equation
+        ));
+
+        assert_eq!(
+            Some(Operands::Range(10u128.pow(15)..=10u128.pow(15))),
+            Power.unapply(10u128.pow(30), 2)
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let message = |line: &str| line.parse::<Equation<u64>>().unwrap_err().to_string();
+
+        assert_eq!(
+            "column 6: invalid number \"1x\": invalid digit found in string",
+            message("190: 1x 19")
+        );
+        assert_eq!(
+            "column 9: invalid number \"\": cannot parse integer from empty string",
+            message("190: 10  19")
+        );
+        assert_eq!(
+            "column 1: invalid test value \"-190\": invalid digit found in string",
+            message("-190: 10 19")
+        );
+        assert_eq!(
+            "column 4: expected \": \" after the test value, found \" 1\"",
+            message("190 10 19")
+        );
+        assert_eq!(
+            "column 4: expected \": \" after the test value, found the end of the line",
+            message("190")
+        );
+        // Columns count characters rather than bytes.
+        assert_eq!(
+            "column 9: invalid number \"1ü\": invalid digit found in string",
+            message("190: 10 1ü")
+        );
+    }
+
+    #[test]
+    fn test_precedence() {
+        let operators: [&dyn Operator<u64>; 3] = [&Add, &Multiply, &CONCATENATION];
+        let highest = Evaluation::Precedence(ConcatenationPrecedence::Highest);
+        let lowest = Evaluation::Precedence(ConcatenationPrecedence::Lowest);
+
+        // `81 + 40 * 27` is 1161 with precedence.
+        let equation: Equation<u64> = "1161: 81 40 27".parse().unwrap();
+        let first = find_solution(Solver::Forward, highest, &operators, &equation).unwrap();
+        assert_eq!(vec!["+", "*"], symbols(&first));
+
+        // Powers apply from the right, so `2 ^ 3 ^ 2` is 512 rather than 64,
+        // while `2 ^ 3 * 2` is still 16.
+        let power: [&dyn Operator<u64>; 2] = [&Power, &Multiply];
+        for (input, possible) in [
+            ("512: 2 3 2", true),
+            ("64: 2 3 2", false),
+            ("16: 2 3 2", true),
+        ] {
+            let equation: Equation<u64> = input.parse().unwrap();
+            assert_eq!(
+                possible,
+                is_possible(Solver::Forward, highest, &power, &equation)
+            );
+        }
+
+        // Only `11 + 6 * 16 + 20` is 292, and only from left to right.
+        let equation: Equation<u64> = "292: 11 6 16 20".parse().unwrap();
+        assert!(!is_possible(
+            Solver::Forward,
+            highest,
+            &operators[..2],
+            &equation
+        ));
+
+        // `6 * 8 || 6 * 15` is 6 * 86 * 15 when concatenation binds highest
+        // and 48 || 90 when it binds lowest.
+        let equation: Equation<u64> = "7740: 6 8 6 15".parse().unwrap();
+        assert!(is_possible(Solver::Forward, highest, &operators, &equation));
+        assert!(!is_possible(Solver::Forward, lowest, &operators, &equation));
+        let equation: Equation<u64> = "4890: 6 8 6 15".parse().unwrap();
+        assert!(is_possible(Solver::Forward, lowest, &operators, &equation));
+        assert!(!is_possible(
+            Solver::Forward,
+            highest,
+            &operators,
+            &equation
+        ));
+    }
+
+    proptest! {
+        #[test]
+        fn test_solvers_agree(
+            test_value in 0..5000u64,
+            numbers in prop::collection::vec(0..30u64, 1..6),
+        ) {
+            // Division is left out, as the backward solver can't use it.
+            let operators: [&dyn Operator<u64>; 7] = [
+                &Add,
+                &Multiply,
+                &CONCATENATION,
+                &Concatenation { base: 2 },
+                &Subtract,
+                &Power,
+                &Xor,
+            ];
+            let equation = Equation { test_value, numbers };
+
+            let mut forward: Vec<Vec<String>> =
+                find_all_solutions(Solver::Forward, Evaluation::LeftToRight, &operators, &equation)
+                    .iter()
+                    .map(|solution| symbols(solution))
+                    .collect();
+            let mut backward: Vec<Vec<String>> =
+                find_all_solutions(Solver::Backward, Evaluation::LeftToRight, &operators, &equation)
+                    .iter()
+                    .map(|solution| symbols(solution))
+                    .collect();
+            forward.sort();
+            backward.sort();
+
+            prop_assert_eq!(forward, backward);
+        }
+    }
 }
@@ -0,0 +1,67 @@
+use std::fmt::{Debug, Display};
+use std::num::ParseIntError;
+use std::ops::BitXor;
+use std::str::FromStr;
+
+/// An unsigned integer type equations can be solved in. Every operation is
+/// checked, so a result that doesn't fit is treated as unreachable rather
+/// than wrapping or panicking.
+pub trait Number:
+    Copy + Ord + Debug + Display + FromStr<Err = ParseIntError> + From<u64> + BitXor<Output = Self>
+{
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn saturating_add(self, rhs: Self) -> Self;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+    fn checked_div(self, rhs: Self) -> Option<Self>;
+    fn checked_pow(self, exp: u32) -> Option<Self>;
+    fn checked_ilog(self, base: Self) -> Option<u32>;
+    fn is_multiple_of(self, rhs: Self) -> bool;
+    fn to_u32(self) -> Option<u32>;
+}
+
+macro_rules! impl_number {
+    ($($t:ty),*) => {
+        $(
+            impl Number for $t {
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_add(self, rhs)
+                }
+
+                fn saturating_add(self, rhs: Self) -> Self {
+                    <$t>::saturating_add(self, rhs)
+                }
+
+                fn checked_sub(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_sub(self, rhs)
+                }
+
+                fn checked_mul(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_mul(self, rhs)
+                }
+
+                fn checked_div(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_div(self, rhs)
+                }
+
+                fn checked_pow(self, exp: u32) -> Option<Self> {
+                    <$t>::checked_pow(self, exp)
+                }
+
+                fn checked_ilog(self, base: Self) -> Option<u32> {
+                    <$t>::checked_ilog(self, base)
+                }
+
+                fn is_multiple_of(self, rhs: Self) -> bool {
+                    <$t>::is_multiple_of(self, rhs)
+                }
+
+                fn to_u32(self) -> Option<u32> {
+                    u32::try_from(self).ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_number!(u64, u128);
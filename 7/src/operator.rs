@@ -0,0 +1,350 @@
+use std::fmt::Debug;
+use std::ops::RangeInclusive;
+
+use crate::number::Number;
+
+/// An operator that can be placed between two numbers of an equation.
+/// Operators are evaluated left to right unless precedence is asked for.
+pub trait Operator<N: Number>: Debug {
+    /// Shown between numbers when printing a solution.
+    fn symbol(&self) -> String;
+
+    /// Returns `None` if the result doesn't fit in an `N`.
+    fn apply(&self, a: N, b: N) -> Option<N>;
+
+    /// Returns every `a` for which `apply(a, b)` is `target`, for the backward
+    /// solver, or `None` if there are none.
+    fn unapply(&self, target: N, b: N) -> Option<Operands<N>>;
+
+    /// Whether the backward solver can use `unapply`, which it can't when
+    /// there may be too many `a` to try.
+    fn can_unapply(&self) -> bool;
+
+    /// Whether `apply(a, b)` is never less than `a` for this `b`, so the
+    /// forward solver can stop once its value passes the test value.
+    fn is_monotonic(&self, b: N) -> bool;
+
+    /// Which group the operator binds with when evaluating with precedence.
+    fn precedence(&self) -> Precedence;
+}
+
+/// The values of `a` that `Operator::unapply` finds.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Operands<N> {
+    /// Every `a` in the range.
+    Range(RangeInclusive<N>),
+    /// Every `a` there is, as when multiplying by zero.
+    Any,
+}
+
+/// Groups of operators that bind equally tightly when evaluating with
+/// precedence, following the usual maths rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precedence {
+    Bitwise,
+    Additive,
+    Multiplicative,
+    Exponent,
+    Concatenation,
+}
+
+impl Precedence {
+    /// Whether operators in the group apply from the right, as `2 ^ 3 ^ 2` is
+    /// `2 ^ 9`. The others apply from the left.
+    pub fn is_right_associative(self) -> bool {
+        self == Precedence::Exponent
+    }
+}
+
+/// Looks up an operator by its command line name: `add`, `multiply`,
+/// `concat`, `subtract`, `divide`, `power`, `xor`, or `concat<base>` for
+/// concatenation in bases 2 to 36, e.g. `concat2`.
+pub fn operator_from_name<N: Number>(name: &str) -> Option<Box<dyn Operator<N>>> {
+    let operator: Box<dyn Operator<N>> = match name {
+        "add" => Box::new(Add),
+        "multiply" => Box::new(Multiply),
+        "concat" => Box::new(Concatenation { base: 10 }),
+        "subtract" => Box::new(Subtract),
+        "divide" => Box::new(Divide),
+        "power" => Box::new(Power),
+        "xor" => Box::new(Xor),
+        _ => {
+            let base: u64 = name.strip_prefix("concat")?.parse().ok()?;
+            if !(2..=36).contains(&base) {
+                return None;
+            }
+
+            Box::new(Concatenation { base })
+        }
+    };
+
+    Some(operator)
+}
+
+fn single<N: Number>(a: Option<N>) -> Option<Operands<N>> {
+    a.map(|a| Operands::Range(a..=a))
+}
+
+#[derive(Debug)]
+pub struct Add;
+
+impl<N: Number> Operator<N> for Add {
+    fn symbol(&self) -> String {
+        "+".to_string()
+    }
+
+    fn apply(&self, a: N, b: N) -> Option<N> {
+        a.checked_add(b)
+    }
+
+    fn unapply(&self, target: N, b: N) -> Option<Operands<N>> {
+        single(target.checked_sub(b))
+    }
+
+    fn can_unapply(&self) -> bool {
+        true
+    }
+
+    fn is_monotonic(&self, _b: N) -> bool {
+        true
+    }
+
+    fn precedence(&self) -> Precedence {
+        Precedence::Additive
+    }
+}
+
+#[derive(Debug)]
+pub struct Multiply;
+
+impl<N: Number> Operator<N> for Multiply {
+    fn symbol(&self) -> String {
+        "*".to_string()
+    }
+
+    fn apply(&self, a: N, b: N) -> Option<N> {
+        a.checked_mul(b)
+    }
+
+    /// Multiplying any `a` by zero gives zero, and nothing else.
+    fn unapply(&self, target: N, b: N) -> Option<Operands<N>> {
+        if b == N::from(0) {
+            return (target == N::from(0)).then_some(Operands::Any);
+        }
+
+        if !target.is_multiple_of(b) {
+            return None;
+        }
+
+        single(target.checked_div(b))
+    }
+
+    fn can_unapply(&self) -> bool {
+        true
+    }
+
+    fn is_monotonic(&self, b: N) -> bool {
+        b != N::from(0)
+    }
+
+    fn precedence(&self) -> Precedence {
+        Precedence::Multiplicative
+    }
+}
+
+/// Appends the digits of `b` to `a`, written in `base`.
+#[derive(Debug)]
+pub struct Concatenation {
+    pub base: u64,
+}
+
+impl Concatenation {
+    /// What `a` is multiplied by to make room for the digits of `b`.
+    fn shift<N: Number>(&self, b: N) -> Option<N> {
+        let base = N::from(self.base);
+        let num_digits = b.checked_ilog(base).unwrap_or(0) + 1;
+        base.checked_pow(num_digits)
+    }
+}
+
+impl<N: Number> Operator<N> for Concatenation {
+    fn symbol(&self) -> String {
+        match self.base {
+            10 => "||".to_string(),
+            base => format!("||{}", base),
+        }
+    }
+
+    fn apply(&self, a: N, b: N) -> Option<N> {
+        if a == N::from(0) {
+            return None;
+        }
+
+        log::trace!("Shift for {} in base {}: {:?}", b, self.base, self.shift(b));
+        a.checked_mul(self.shift(b)?)?.checked_add(b)
+    }
+
+    fn unapply(&self, target: N, b: N) -> Option<Operands<N>> {
+        let shift = self.shift(b)?;
+
+        // `apply` never concatenates onto zero.
+        let rest = target.checked_sub(b).filter(|rest| *rest != N::from(0))?;
+        if !rest.is_multiple_of(shift) {
+            return None;
+        }
+
+        single(rest.checked_div(shift))
+    }
+
+    fn can_unapply(&self) -> bool {
+        true
+    }
+
+    fn is_monotonic(&self, _b: N) -> bool {
+        true
+    }
+
+    fn precedence(&self) -> Precedence {
+        Precedence::Concatenation
+    }
+}
+
+/// Results below zero aren't reachable.
+#[derive(Debug)]
+pub struct Subtract;
+
+impl<N: Number> Operator<N> for Subtract {
+    fn symbol(&self) -> String {
+        "-".to_string()
+    }
+
+    fn apply(&self, a: N, b: N) -> Option<N> {
+        a.checked_sub(b)
+    }
+
+    fn unapply(&self, target: N, b: N) -> Option<Operands<N>> {
+        single(target.checked_add(b))
+    }
+
+    fn can_unapply(&self) -> bool {
+        true
+    }
+
+    fn is_monotonic(&self, _b: N) -> bool {
+        false
+    }
+
+    fn precedence(&self) -> Precedence {
+        Precedence::Additive
+    }
+}
+
+/// Integer division, rounding down. Undoing it gives `b` values of `a`, too
+/// many to try for large `b`, so the backward solver can't use it.
+#[derive(Debug)]
+pub struct Divide;
+
+impl<N: Number> Operator<N> for Divide {
+    fn symbol(&self) -> String {
+        "/".to_string()
+    }
+
+    fn apply(&self, a: N, b: N) -> Option<N> {
+        a.checked_div(b)
+    }
+
+    fn unapply(&self, target: N, b: N) -> Option<Operands<N>> {
+        let remainder = b.checked_sub(N::from(1))?;
+        let start = target.checked_mul(b)?;
+        Some(Operands::Range(start..=start.saturating_add(remainder)))
+    }
+
+    fn can_unapply(&self) -> bool {
+        false
+    }
+
+    fn is_monotonic(&self, _b: N) -> bool {
+        false
+    }
+
+    fn precedence(&self) -> Precedence {
+        Precedence::Multiplicative
+    }
+}
+
+/// Raises `a` to the power of `b`. A power of zero isn't allowed, as every
+/// `a` would give one.
+#[derive(Debug)]
+pub struct Power;
+
+impl<N: Number> Operator<N> for Power {
+    fn symbol(&self) -> String {
+        "^".to_string()
+    }
+
+    fn apply(&self, a: N, b: N) -> Option<N> {
+        if b == N::from(0) {
+            return None;
+        }
+
+        a.checked_pow(b.to_u32()?)
+    }
+
+    fn unapply(&self, target: N, b: N) -> Option<Operands<N>> {
+        let exp = b.to_u32().filter(|exp| *exp > 0)?;
+
+        // Binary search for the root, as floats can't represent every `N`.
+        let (mut low, mut high) = (N::from(0), target);
+        while low < high {
+            let mid = low.checked_add(high.checked_sub(low)?.checked_div(N::from(2))?)?;
+            match mid.checked_pow(exp) {
+                Some(power) if power < target => low = mid.checked_add(N::from(1))?,
+                _ => high = mid,
+            }
+        }
+
+        single(Some(low).filter(|a| a.checked_pow(exp) == Some(target)))
+    }
+
+    fn can_unapply(&self) -> bool {
+        true
+    }
+
+    fn is_monotonic(&self, _b: N) -> bool {
+        true
+    }
+
+    fn precedence(&self) -> Precedence {
+        Precedence::Exponent
+    }
+}
+
+/// Bitwise exclusive or.
+#[derive(Debug)]
+pub struct Xor;
+
+impl<N: Number> Operator<N> for Xor {
+    fn symbol(&self) -> String {
+        "xor".to_string()
+    }
+
+    fn apply(&self, a: N, b: N) -> Option<N> {
+        Some(a ^ b)
+    }
+
+    fn unapply(&self, target: N, b: N) -> Option<Operands<N>> {
+        single(Some(target ^ b))
+    }
+
+    fn can_unapply(&self) -> bool {
+        true
+    }
+
+    fn is_monotonic(&self, _b: N) -> bool {
+        false
+    }
+
+    fn precedence(&self) -> Precedence {
+        Precedence::Bitwise
+    }
+}
@@ -1,8 +1,9 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::env;
+use std::fmt::Display;
 use std::fs;
-use std::i32;
+use std::process;
 use std::str::FromStr;
 
 #[derive(Debug, Hash, PartialEq, Eq)]
@@ -32,8 +33,17 @@ impl Map {
                 )
             })
             .inspect(|(frequency, locations)| log::trace!("{:?}: {:?}", frequency, locations))
-            .map(|(_, locations)| locations)
-            .flatten()
+            .flat_map(|(_, locations)| locations)
+            .collect()
+    }
+
+    /// Like `find_antinode_locations` with every harmonic, but finds every
+    /// position on the lines through each pair of antennas, including those
+    /// between harmonics.
+    fn find_collinear_locations(&self) -> HashSet<Position> {
+        self.antennas
+            .values()
+            .flat_map(|locations| find_collinear_locations(locations, self.width, self.height))
             .collect()
     }
 }
@@ -95,9 +105,96 @@ fn find_antinode_locations(
     antinode_locations
 }
 
+/// Finds every position within bounds on the line through each pair of
+/// `locations`, by stepping from one antenna towards the other in the
+/// smallest steps that land on whole positions.
+fn find_collinear_locations(locations: &[Position], width: u16, height: u16) -> HashSet<Position> {
+    let mut collinear_locations = HashSet::new();
+    let in_bounds = |x: i32, y: i32| x >= 0 && y >= 0 && x < width as i32 && y < height as i32;
+
+    for i in 0..locations.len() {
+        for j in i + 1..locations.len() {
+            let antenna_x = locations[i].x as i32;
+            let antenna_y = locations[i].y as i32;
+
+            let delta_x = locations[j].x as i32 - antenna_x;
+            let delta_y = locations[j].y as i32 - antenna_y;
+            let divisor = gcd(delta_x, delta_y);
+            let (step_x, step_y) = (delta_x / divisor, delta_y / divisor);
+
+            for (step_x, step_y) in [(step_x, step_y), (-step_x, -step_y)] {
+                let (mut x, mut y) = (antenna_x, antenna_y);
+                while in_bounds(x, y) {
+                    collinear_locations.insert(Position {
+                        x: x as u16,
+                        y: y as u16,
+                    });
+                    x += step_x;
+                    y += step_y;
+                }
+            }
+        }
+    }
+
+    collinear_locations
+}
+
+fn gcd(a: i32, b: i32) -> i32 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// How antinodes are found for the second part, stepped unless `--collinear`
+/// follows the input path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Harmonics {
+    /// Steps from each antenna by whole multiples of the distance to the
+    /// other.
+    Stepped,
+    /// Includes every position on the line through both antennas.
+    Collinear,
+}
+
 #[derive(Debug)]
 enum ParseMapError {
-    UnequalLineLenghts,
+    UnequalLineLenghts {
+        line: usize,
+        column: usize,
+        expected: u16,
+        got: u16,
+        source_line: String,
+    },
+}
+
+impl Display for ParseMapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseMapError::UnequalLineLenghts {
+                line,
+                column,
+                expected,
+                got,
+                source_line,
+            } => {
+                f.write_fmt(format_args!(
+                    "got unequal line lengths, expected {}, got {}\n",
+                    expected, got
+                ))?;
+
+                let gutter = " ".repeat(line.to_string().len());
+                f.write_fmt(format_args!(
+                    "{} --> line {}, column {}\n",
+                    gutter, line, column
+                ))?;
+                f.write_fmt(format_args!("{} |\n", gutter))?;
+                f.write_fmt(format_args!("{} | {}\n", line, source_line))?;
+                f.write_fmt(format_args!("{} | {}^", gutter, " ".repeat(column - 1)))
+            }
+        }
+    }
 }
 
 impl FromStr for Map {
@@ -115,7 +212,13 @@ impl FromStr for Map {
                 '\n' => {
                     if let Some(existing_width) = width {
                         if x != existing_width {
-                            return Err(ParseMapError::UnequalLineLenghts);
+                            return Err(ParseMapError::UnequalLineLenghts {
+                                line: y as usize + 1,
+                                column: x.min(existing_width) as usize + 1,
+                                expected: existing_width,
+                                got: x,
+                                source_line: s.split('\n').nth(y as usize).unwrap().to_string(),
+                            });
                         }
                     } else {
                         width = Some(x);
@@ -139,7 +242,8 @@ impl FromStr for Map {
             x += 1;
         }
 
-        let height = y + 1;
+        // A trailing newline ends the last row rather than starting another.
+        let height = if x == 0 && y > 0 { y } else { y + 1 };
 
         Ok(Map {
             antennas,
@@ -163,10 +267,23 @@ fn load_input() -> String {
 fn main() {
     env_logger::init();
 
+    let args: Vec<String> = env::args().collect();
+    let harmonics = match args.get(2).map(String::as_str) {
+        None => Harmonics::Stepped,
+        Some("--collinear") => Harmonics::Collinear,
+        Some(_) => {
+            eprintln!("usage: eight <input> [--collinear]");
+            process::exit(1);
+        }
+    };
+
     let input = load_input();
     log::debug!("\n{}", input);
 
-    let map: Map = input.parse().unwrap();
+    let map: Map = input.parse().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
     log::debug!("{:?}", map);
 
     let antinode_locations = map.find_antinode_locations(2, 3);
@@ -174,8 +291,32 @@ fn main() {
     let num_antinode_locations = antinode_locations.len();
     println!("{}", num_antinode_locations);
 
-    let antinode_locations = map.find_antinode_locations(1, i32::MAX);
+    let antinode_locations = match harmonics {
+        Harmonics::Stepped => map.find_antinode_locations(1, i32::MAX),
+        Harmonics::Collinear => map.find_collinear_locations(),
+    };
     log::debug!("{:?}", antinode_locations);
     let num_antinode_locations = antinode_locations.len();
     println!("{}", num_antinode_locations);
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::Map;
+
+    #[test]
+    fn test_collinear() {
+        // The antennas are two steps apart diagonally, so stepping by the
+        // whole distance skips (1, 1) and (3, 3).
+        let map: Map = "a....\n.....\n..a..\n.....\n.....\n".parse().unwrap();
+
+        assert_eq!(3, map.find_antinode_locations(1, i32::MAX).len());
+        assert_eq!(5, map.find_collinear_locations().len());
+
+        // The line through both antennas runs down the first column, which
+        // ends at the last row despite the trailing newline.
+        let map: Map = "a....\n.....\na....\n.....\n.....\n".parse().unwrap();
+        assert_eq!(5, map.height);
+        assert_eq!(5, map.find_collinear_locations().len());
+    }
+}